@@ -16,6 +16,10 @@ pub struct Project {
     files: Vec<(String, String)>,
     debug: bool,
     uglify: bool,
+    js: bool,
+    nodejs: bool,
+    serde: bool,
+    bigint: bool,
 }
 
 pub fn project() -> Project {
@@ -29,35 +33,27 @@ pub fn project() -> Project {
     Project {
         debug: true,
         uglify: false,
+        js: false,
+        nodejs: true,
+        serde: false,
+        bigint: false,
         files: vec![
-            ("Cargo.toml".to_string(), format!(r#"
-                [package]
-                name = "test{}"
-                version = "0.0.1"
-                authors = []
-
-                [workspace]
-
-                [lib]
-                crate-type = ["cdylib"]
-
-                [dependencies]
-                wasm-bindgen = {{ path = '{}' }}
-
-                [profile.dev]
-                opt-level = 2 # TODO: decrease when upstream is not buggy
-                incremental = false
-            "#, IDX.with(|x| *x), dir.display())),
-
             ("Cargo.lock".to_string(), lockfile),
 
             ("run.ts".to_string(), r#"
                 import * as fs from "fs";
                 import * as process from "process";
+                import * as util from "util";
 
                 import { instantiate } from "./out";
                 import * as test from "./test";
 
+                // Output generated for the browser expects these globals
+                if (typeof((global as any).TextEncoder) === 'undefined') {
+                  (global as any).TextEncoder = (util as any).TextEncoder;
+                  (global as any).TextDecoder = (util as any).TextDecoder;
+                }
+
                 var wasm = fs.readFileSync("out.wasm");
 
                 instantiate(wasm, test.imports).then(m => {
@@ -133,9 +129,68 @@ impl Project {
         self
     }
 
+    pub fn js(&mut self, js: bool) -> &mut Project {
+        self.js = js;
+        self
+    }
+
+    pub fn nodejs(&mut self, nodejs: bool) -> &mut Project {
+        self.nodejs = nodejs;
+        self
+    }
+
+    pub fn serde(&mut self, serde: bool) -> &mut Project {
+        self.serde = serde;
+        self
+    }
+
+    pub fn bigint(&mut self, bigint: bool) -> &mut Project {
+        self.bigint = bigint;
+        self
+    }
+
+    fn manifest(&self) -> String {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let dir = dir.parent().unwrap() // chop off `test-support`
+            .parent().unwrap(); // chop off `crates`
+
+        let mut manifest = format!(r#"
+            [package]
+            name = "test{}"
+            version = "0.0.1"
+            authors = []
+
+            [workspace]
+
+            [lib]
+            crate-type = ["cdylib"]
+
+            [profile.dev]
+            opt-level = 2 # TODO: decrease when upstream is not buggy
+            incremental = false
+
+            [dependencies]
+        "#, IDX.with(|x| *x));
+        if self.serde {
+            manifest.push_str(&format!("
+                wasm-bindgen = {{ path = '{}', features = ['serde-serialize'] }}
+                serde = '1.0'
+                serde_derive = '1.0'
+            ", dir.display()));
+        } else {
+            manifest.push_str(&format!("
+                wasm-bindgen = {{ path = '{}' }}
+            ", dir.display()));
+        }
+        manifest
+    }
+
     pub fn test(&mut self) {
         let root = root();
         drop(fs::remove_dir_all(&root));
+        fs::create_dir_all(&root).unwrap();
+        fs::File::create(root.join("Cargo.toml")).unwrap()
+            .write_all(self.manifest().as_bytes()).unwrap();
         for &(ref file, ref contents) in self.files.iter() {
             let dst = root.join(file);
             fs::create_dir_all(dst.parent().unwrap()).unwrap();
@@ -165,12 +220,35 @@ impl Project {
 
         let obj = cli::Bindgen::new()
             .input_path(&out)
-            .nodejs(true)
+            .nodejs(self.nodejs)
             .debug(self.debug)
+            .bigint(self.bigint)
             .uglify_wasm_names(self.uglify)
             .generate()
             .expect("failed to run bindgen");
-        obj.write_ts_to(root.join("out.ts")).expect("failed to write ts");
+        if self.js && !self.nodejs {
+            // node can't load an ES module directly, so compile it down to a
+            // CommonJS `out.js` first
+            obj.write_js_to(root.join("es/out.js")).expect("failed to write js");
+            obj.write_dts_to(root.join("out.d.ts")).expect("failed to write d.ts");
+            let mut cmd = Command::new("node");
+            cmd.arg(typescript())
+                .current_dir(&target_dir)
+                .arg(root.join("es/out.js"))
+                .arg("--allowJs")
+                .arg("--target")
+                .arg("es6")
+                .arg("--module")
+                .arg("commonjs")
+                .arg("--outDir")
+                .arg(&root);
+            run(&mut cmd, "node");
+        } else if self.js {
+            obj.write_js_to(root.join("out.js")).expect("failed to write js");
+            obj.write_dts_to(root.join("out.d.ts")).expect("failed to write d.ts");
+        } else {
+            obj.write_ts_to(root.join("out.ts")).expect("failed to write ts");
+        }
         obj.write_wasm_to(root.join("out.wasm")).expect("failed to write wasm");
 
         let mut cmd = Command::new("node");
@@ -186,7 +264,8 @@ impl Project {
             .arg("--noImplicitReturns")
             .arg("--declaration")
             .arg("--lib")
-            .arg("es6");
+            // `BigInt` is only declared by the `esnext` library
+            .arg(if self.bigint { "esnext" } else { "es6" });
         run(&mut cmd, "node");
 
         let mut cmd = Command::new("node");
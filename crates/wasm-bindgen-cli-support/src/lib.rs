@@ -22,6 +22,7 @@ pub struct Bindgen {
     nodejs: bool,
     debug: bool,
     uglify: bool,
+    bigint: bool,
 }
 
 pub struct Object {
@@ -29,6 +30,7 @@ pub struct Object {
     program: shared::Program,
     nodejs: bool,
     debug: bool,
+    bigint: bool,
 }
 
 impl Bindgen {
@@ -38,6 +40,7 @@ impl Bindgen {
             nodejs: false,
             debug: false,
             uglify: false,
+            bigint: false,
         }
     }
 
@@ -61,6 +64,13 @@ impl Bindgen {
         self
     }
 
+    /// Whether `i64` and `u64` values show up in JS as a `BigInt` rather than
+    /// a `number`, which loses precision beyond 2^53.
+    pub fn bigint(&mut self, bigint: bool) -> &mut Bindgen {
+        self.bigint = bigint;
+        self
+    }
+
     pub fn generate(&mut self) -> Result<Object, Error> {
         let input = match self.path {
             Some(ref path) => path,
@@ -83,6 +93,7 @@ impl Bindgen {
             program,
             nodejs: self.nodejs,
             debug: self.debug,
+            bigint: self.bigint,
         })
     }
 }
@@ -94,13 +105,25 @@ impl Object {
 
     fn _write_ts_to(&self, path: &Path) -> Result<(), Error> {
         let ts = self.generate_ts();
-        let mut f = File::create(path).with_context(|_| {
-            format!("failed to create file at {:?}", path)
-        })?;
-        f.write_all(ts.as_bytes()).with_context(|_| {
-            format!("failed to write file at {:?}", path)
-        })?;
-        Ok(())
+        write(path, &ts)
+    }
+
+    pub fn write_js_to<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        self._write_js_to(path.as_ref())
+    }
+
+    fn _write_js_to(&self, path: &Path) -> Result<(), Error> {
+        let js = self.generate_js();
+        write(path, &js)
+    }
+
+    pub fn write_dts_to<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        self._write_dts_to(path.as_ref())
+    }
+
+    fn _write_dts_to(&self, path: &Path) -> Result<(), Error> {
+        let dts = self.generate_dts();
+        write(path, &dts)
     }
 
     pub fn write_wasm_to<P: AsRef<Path>>(self, path: P) -> Result<(), Error> {
@@ -115,14 +138,45 @@ impl Object {
     }
 
     pub fn generate_ts(&self) -> String {
+        self.generate(true).to_string(&self.module, &self.program)
+    }
+
+    /// Generates plain ES2015 JS bindings, equivalent to the TypeScript
+    /// returned by `generate_ts` but without any type annotations.
+    pub fn generate_js(&self) -> String {
+        self.generate(false).to_string(&self.module, &self.program)
+    }
+
+    /// Generates a TypeScript declaration file describing the `Exports` and
+    /// `Imports` interfaces and each exported class, suitable to ship next
+    /// to the output of `generate_js`.
+    pub fn generate_dts(&self) -> String {
+        let mut ts = self.generate(false);
+        ts.to_string(&self.module, &self.program);
+        ts.declarations
+    }
+
+    fn generate(&self, typescript: bool) -> ts::Js {
         let mut ts = ts::Js::default();
         ts.nodejs = self.nodejs;
         ts.debug = self.debug;
+        ts.typescript = typescript;
+        ts.bigint = self.bigint;
         ts.generate_program(&self.program, &self.module);
-        ts.to_string(&self.module, &self.program)
+        ts
     }
 }
 
+fn write(path: &Path, contents: &str) -> Result<(), Error> {
+    let mut f = File::create(path).with_context(|_| {
+        format!("failed to create file at {:?}", path)
+    })?;
+    f.write_all(contents.as_bytes()).with_context(|_| {
+        format!("failed to write file at {:?}", path)
+    })?;
+    Ok(())
+}
+
 fn extract_program(module: &mut Module) -> shared::Program {
     let data = module.sections_mut()
         .iter_mut()
@@ -138,6 +192,8 @@ fn extract_program(module: &mut Module) -> shared::Program {
         structs: Vec::new(),
         free_functions: Vec::new(),
         imports: Vec::new(),
+        enums: Vec::new(),
+        consts: Vec::new(),
     };
     let data = match data {
         Some(data) => data,
@@ -155,10 +211,18 @@ fn extract_program(module: &mut Module) -> shared::Program {
                 Ok(f) => f,
                 Err(_) => continue,
             };
-            let shared::Program { structs, free_functions, imports } = p;
+            let shared::Program {
+                structs,
+                free_functions,
+                imports,
+                enums,
+                consts,
+            } = p;
             ret.structs.extend(structs);
             ret.free_functions.extend(free_functions);
             ret.imports.extend(imports);
+            ret.enums.extend(enums);
+            ret.consts.extend(consts);
         }
         data.entries_mut().remove(i);
     }
@@ -41,7 +41,7 @@ impl Mapped {
         };
 
         for import in program.imports.iter() {
-            self.imports.insert(import.name.clone(), generate());
+            self.imports.insert(import.shim_name(), generate());
         }
 
         for f in program.free_functions.iter() {
@@ -1,4 +1,4 @@
-use std::collections::{HashSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashSet, HashMap};
 
 use shared;
 use parity_wasm::elements::*;
@@ -11,9 +11,14 @@ pub struct Js {
     exposed_globals: HashSet<&'static str>,
     exports: Vec<(String, String, String)>,
     wasm_exports_bound: HashSet<String>,
-    classes: Vec<String>,
+    classes: Vec<(String, String)>,
+    class_declarations: Vec<String>,
+    module_imports: BTreeMap<String, (usize, BTreeSet<String>)>,
+    pub declarations: String,
     pub nodejs: bool,
     pub debug: bool,
+    pub typescript: bool,
+    pub bigint: bool,
 }
 
 impl Js {
@@ -27,6 +32,50 @@ impl Js {
         for s in program.structs.iter() {
             self.generate_struct(s, m);
         }
+        for e in program.enums.iter() {
+            self.generate_enum(e);
+        }
+        for c in program.consts.iter() {
+            self.generate_const(c);
+        }
+    }
+
+    pub fn generate_const(&mut self, c: &shared::Const) {
+        let (value, ty) = match c.value {
+            shared::ConstValue::Number(n) => (n.to_string(), "number"),
+            shared::ConstValue::Boolean(b) => (b.to_string(), "boolean"),
+            shared::ConstValue::String(ref s) => {
+                (::serde_json::to_string(s).unwrap(), "string")
+            }
+        };
+        let ts_export = format!("readonly {}: {};", c.name, ty);
+        self.exports.push((c.name.clone(), value, ts_export));
+    }
+
+    pub fn generate_enum(&mut self, e: &shared::Enum) {
+        let mut variants = String::new();
+        for v in e.variants.iter() {
+            if self.typescript {
+                variants.push_str(&format!("{} = {},\n", v.name, v.value));
+            } else {
+                variants.push_str(&format!("{}: {},\n", v.name, v.value));
+            }
+        }
+        let dst = if self.typescript {
+            format!("enum {} {{\n{}}}\n", e.name, variants)
+        } else {
+            format!("const {} = Object.freeze({{\n{}}});\n", e.name, variants)
+        };
+        let decl = format!("export declare enum {} {{\n{}}}\n",
+                           e.name,
+                           e.variants.iter()
+                               .map(|v| format!("{} = {},\n", v.name, v.value))
+                               .collect::<String>());
+        self.classes.push((e.name.clone(), dst));
+        self.class_declarations.push(decl);
+
+        let ts_export = format!("{0}: typeof {0};", e.name);
+        self.exports.push((e.name.clone(), e.name.clone(), ts_export));
     }
 
     pub fn generate_free_function(&mut self,
@@ -36,6 +85,7 @@ impl Js {
                                               &func.name,
                                               &func.name,
                                               false,
+                                              false,
                                               &func.arguments,
                                               func.ret.as_ref(),
                                               m);
@@ -47,61 +97,149 @@ impl Js {
                            s: &shared::Struct,
                            m: &Mapped) {
         let mut dst = String::new();
+        let mut decl = String::new();
         self.expose_wasm_exports();
         dst.push_str(&format!("
-            export class {} {{
+            class {} {{
         ", s.name));
-        if self.debug {
-            self.expose_check_token();
-            dst.push_str(&format!("
-                constructor(public ptr: number, sym: Symbol) {{
-                    _checkToken(sym);
-                }}
-            "));
-        } else {
-            dst.push_str(&format!("
-                constructor(public ptr: number) {{}}
-            "));
+        decl.push_str(&format!("
+            export declare class {} {{
+                ptr: number;
+        ", s.name));
+        if self.typescript {
+            dst.push_str("ptr: number;\n");
         }
 
+        // Instances wrapping a pointer handed out by Rust are created without
+        // running the JS constructor, which is reserved for the Rust
+        // constructor if there is one.
         dst.push_str(&format!("
-            free(): void {{
+            static __construct(ptr{}){} {{
+                const obj = Object.create({}.prototype);
+                obj.ptr = ptr;
+                return obj;
+            }}
+        ", self.ty("number"), self.ty(&s.name), s.name));
+
+        let constructor = s.constructor.as_ref().map(|name| {
+            s.functions.iter()
+                .find(|f| f.name == *name)
+                .expect("constructor isn't a function of the struct")
+        });
+        match constructor {
+            Some(function) => {
+                let (js, ts) = self.generate_function(
+                    "constructor",
+                    "constructor",
+                    &function.struct_function_export_name(&s.name),
+                    false,
+                    false,
+                    &function.arguments,
+                    function.ret.as_ref(),
+                    m,
+                );
+                dst.push_str(&js);
+                dst.push_str("\n");
+                decl.push_str(&ts);
+                decl.push_str("\n");
+            }
+            None => {
+                decl.push_str("private constructor();\n");
+                if self.debug {
+                    dst.push_str("
+                        constructor() {
+                            throw new Error('cannot invoke `new` directly');
+                        }
+                    ");
+                }
+            }
+        }
+        decl.push_str("free(): void;\n");
+
+        dst.push_str(&format!("
+            free(){} {{
                 const ptr = this.ptr;
                 this.ptr = 0;
                 wasm_exports.{}(ptr);
             }}
-        ", m.export_name(&s.free_function())));
+        ", self.ty("void"), m.export_name(&s.free_function())));
 
         self.wasm_exports_bound.insert(s.name.clone());
 
         for function in s.functions.iter() {
-            let (js, _ts) = self.generate_function(
+            if s.constructor.as_ref() == Some(&function.name) {
+                continue
+            }
+            let (js, ts) = self.generate_function(
                 "static",
                 &function.name,
                 &function.struct_function_export_name(&s.name),
                 false,
+                false,
                 &function.arguments,
                 function.ret.as_ref(),
                 m,
             );
             dst.push_str(&js);
             dst.push_str("\n");
+            decl.push_str("static ");
+            decl.push_str(&ts);
+            decl.push_str("\n");
         }
         for method in s.methods.iter() {
-            let (js, _ts) = self.generate_function(
+            let (js, ts) = self.generate_function(
                 "",
                 &method.function.name,
                 &method.function.struct_function_export_name(&s.name),
                 true,
+                method.consume,
                 &method.function.arguments,
                 method.function.ret.as_ref(),
                 m,
             );
             dst.push_str(&js);
             dst.push_str("\n");
+            decl.push_str(&ts);
+            decl.push_str("\n");
+        }
+        for field in s.fields.iter() {
+            let (getter, _) = self.generate_function(
+                "get",
+                &field.name,
+                &s.field_getter(&field.name),
+                true,
+                false,
+                &[],
+                Some(&field.ty),
+                m,
+            );
+            let (setter, _) = self.generate_function(
+                "set",
+                &field.name,
+                &s.field_setter(&field.name),
+                true,
+                false,
+                &[field.ty.clone()],
+                None,
+                m,
+            );
+            dst.push_str(&getter);
+            dst.push_str("\n");
+            dst.push_str(&setter);
+            dst.push_str("\n");
+            let ty = match field.ty {
+                shared::Type::Number => "number",
+                shared::Type::Boolean => "boolean",
+                shared::Type::String => "string",
+                shared::Type::JsObject => "any",
+                _ => panic!("unsupported field type"),
+            };
+            decl.push_str(&format!("{}: {};\n", field.name, ty));
         }
         dst.push_str("}\n");
-        self.classes.push(dst);
+        decl.push_str("}\n");
+        self.classes.push((s.name.clone(), dst));
+        self.class_declarations.push(decl);
 
         let ts_export = format!("{0}: typeof {0};", s.name);
         self.exports.push((s.name.clone(), s.name.clone(), ts_export));
@@ -112,25 +250,33 @@ impl Js {
                          name: &str,
                          wasm_name: &str,
                          is_method: bool,
+                         consume: bool,
                          arguments: &[shared::Type],
                          ret: Option<&shared::Type>,
                          m: &Mapped) -> (String, String) {
         let mut dst = format!("{}(", name);
+        let mut dst_js = format!("{}(", name);
         let mut passed_args = String::new();
         let mut arg_conversions = String::new();
         let mut destructors = String::new();
 
         if is_method {
-            passed_args.push_str("this.ptr");
+            if consume {
+                passed_args.push_str("me");
+            } else {
+                passed_args.push_str("this.ptr");
+            }
         }
 
         for (i, arg) in arguments.iter().enumerate() {
             let name = format!("arg{}", i);
             if i > 0 {
                 dst.push_str(", ");
+                dst_js.push_str(", ");
             }
             dst.push_str(&name);
             dst.push_str(": ");
+            dst_js.push_str(&name);
 
             let mut pass = |arg: &str| {
                 if passed_args.len() > 0 {
@@ -174,6 +320,33 @@ impl Js {
                         ", i = i, free = m.export_name("__wbindgen_free")));
                     }
                 }
+                shared::Type::BorrowedSlice(kind) |
+                shared::Type::BorrowedMutSlice(kind) |
+                shared::Type::Vector(kind) => {
+                    dst.push_str(typed_array(kind));
+                    let pass_array = self.expose_pass_array_to_wasm(kind, m);
+                    arg_conversions.push_str(&format!("\
+                        const [ptr{i}, len{i}] = {pass_array}({arg});
+                    ", i = i, arg = name, pass_array = pass_array));
+                    pass(&format!("ptr{}", i));
+                    pass(&format!("len{}", i));
+                    if let shared::Type::BorrowedMutSlice(_) = *arg {
+                        let get_array = self.expose_get_array_from_wasm(kind);
+                        destructors.push_str(&format!("\n\
+                            {arg}.set({get_array}(ptr{i}, len{i}));\n\
+                        ", i = i, arg = name, get_array = get_array));
+                    }
+                    match *arg {
+                        shared::Type::BorrowedSlice(_) |
+                        shared::Type::BorrowedMutSlice(_) => {
+                            self.expose_wasm_exports();
+                            destructors.push_str(&format!("\n\
+                                wasm_exports.{free}(ptr{i}, len{i});\n\
+                            ", i = i, free = m.export_name(&kind.free_function())));
+                        }
+                        _ => {}
+                    }
+                }
                 shared::Type::ByRef(ref s) |
                 shared::Type::ByMutRef(ref s) => {
                     dst.push_str(s);
@@ -216,18 +389,154 @@ impl Js {
                     destructors.push_str("stack.pop();\n");
                     pass(&format!("idx{}", i));
                 }
+                shared::Type::Option(ref t) => {
+                    match **t {
+                        shared::Type::Number => {
+                            dst.push_str("number | undefined");
+                            pass(&format!("{} === undefined ? 0 : 1", name));
+                            pass(&format!("{0} === undefined ? 0 : {0}", name));
+                        }
+                        shared::Type::Boolean => {
+                            dst.push_str("boolean | undefined");
+                            pass(&format!("{0} === undefined ? 2 : ({0} ? 1 : 0)", name));
+                        }
+                        shared::Type::BorrowedStr |
+                        shared::Type::String => {
+                            dst.push_str("string | undefined");
+                            self.expose_pass_string_to_wasm(m);
+                            arg_conversions.push_str(&format!("\
+                                const [ptr{i}, len{i}] = {arg} === undefined ?
+                                    [0, 0] :
+                                    passStringToWasm({arg});
+                            ", i = i, arg = name));
+                            pass(&format!("ptr{}", i));
+                            pass(&format!("len{}", i));
+                            if let shared::Type::BorrowedStr = **t {
+                                self.expose_wasm_exports();
+                                destructors.push_str(&format!("\n\
+                                    if (ptr{i} !== 0)\n\
+                                        wasm_exports.{free}(ptr{i}, len{i});\n\
+                                ", i = i, free = m.export_name("__wbindgen_free")));
+                            }
+                        }
+                        shared::Type::JsObject => {
+                            dst.push_str("any");
+                            self.expose_add_heap_object();
+                            arg_conversions.push_str(&format!("\
+                                const idx{i} = {arg} === undefined ? -1 : addHeapObject({arg});
+                            ", i = i, arg = name));
+                            pass(&format!("idx{}", i));
+                        }
+                        shared::Type::ByValue(ref s) => {
+                            dst.push_str(&format!("{} | undefined", s));
+                            let assert_class = if self.debug {
+                                self.expose_assert_class();
+                                format!("_assertClass({}, {});", name, s)
+                            } else {
+                                String::new()
+                            };
+                            arg_conversions.push_str(&format!("\
+                                let ptr{i} = 0;
+                                if ({arg} !== undefined) {{
+                                    {assert_class}
+                                    ptr{i} = {arg}.ptr;
+                                    {arg}.ptr = 0;
+                                }}
+                            ", i = i, arg = name, assert_class = assert_class));
+                            pass(&format!("ptr{}", i));
+                        }
+                        _ => panic!("unsupported type inside of `Option`"),
+                    }
+                }
+                shared::Type::Char => {
+                    dst.push_str("string");
+                    if self.debug {
+                        self.expose_assert_char();
+                        arg_conversions.push_str(&format!("_assertChar({});\n", name));
+                    }
+                    self.expose_char_to_wasm();
+                    pass(&format!("charToWasm({})", name))
+                }
+                shared::Type::I64 |
+                shared::Type::U64 => {
+                    dst.push_str(self.int64_ty());
+                    let to_wasm = self.expose_int64_to_wasm();
+                    arg_conversions.push_str(&format!("\
+                        const [lo{i}, hi{i}] = {to_wasm}({arg});
+                    ", i = i, arg = name, to_wasm = to_wasm));
+                    pass(&format!("lo{0}, hi{0}", i));
+                }
+                shared::Type::Result(_) => {
+                    panic!("`Result` can only be used as a return type")
+                }
+                shared::Type::Closure(_) => {
+                    panic!("closures can only be passed to imported functions")
+                }
+                shared::Type::Enum(ref e) => {
+                    dst.push_str(e);
+                    if self.debug {
+                        self.expose_assert_num();
+                        arg_conversions.push_str(&format!("_assertNum({});\n", name));
+                    }
+                    pass(&name)
+                }
             }
         }
+        if is_method && consume {
+            // The Rust object is moved out of its box, so later uses of this
+            // object throw as if it had been freed
+            arg_conversions.push_str("\
+                const me = this.ptr;
+                this.ptr = 0;
+            ");
+        }
+        let sig_len = dst.len();
         dst.push_str("): ");
+        // An `Err` is thrown from the glue, only the `Ok` type is visible
+        let (ret, catch_err) = match ret {
+            Some(&shared::Type::Result(ref t)) => {
+                self.expose_get_global_argument(m);
+                self.expose_take_object();
+                let catch_err = "\
+                    if (getGlobalArgument(1) !== 0)
+                        throw takeObject(getGlobalArgument(2));
+                ";
+                (t.as_ref().map(|t| &**t), catch_err)
+            }
+            ret => (ret, ""),
+        };
         let convert_ret = match ret {
             None => {
                 dst.push_str("void");
-                format!("return ret;")
+                if prefix == "set" {
+                    String::new()
+                } else {
+                    format!("return ret;")
+                }
             }
             Some(&shared::Type::Number) => {
                 dst.push_str("number");
                 format!("return ret;")
             }
+            Some(&shared::Type::Enum(ref e)) => {
+                dst.push_str(e);
+                format!("return ret;")
+            }
+            Some(&shared::Type::Char) => {
+                dst.push_str("string");
+                format!("return String.fromCodePoint(ret);")
+            }
+            Some(&shared::Type::I64) |
+            Some(&shared::Type::U64) => {
+                dst.push_str(self.int64_ty());
+                let signed = match ret {
+                    Some(&shared::Type::I64) => true,
+                    _ => false,
+                };
+                let from_wasm = self.expose_int64_from_wasm(signed);
+                self.expose_get_global_argument(m);
+                format!("return {}(ret, getGlobalArgument(0));", from_wasm)
+            }
             Some(&shared::Type::Boolean) => {
                 dst.push_str("boolean");
                 format!("return ret != 0;")
@@ -238,20 +547,21 @@ impl Js {
                 format!("return takeObject(ret);")
             }
             Some(&shared::Type::JsObjectRef) |
+            Some(&shared::Type::Result(_)) |
             Some(&shared::Type::BorrowedStr) |
+            Some(&shared::Type::BorrowedSlice(_)) |
+            Some(&shared::Type::BorrowedMutSlice(_)) |
             Some(&shared::Type::ByMutRef(_)) |
             Some(&shared::Type::ByRef(_)) => panic!(),
+            Some(&shared::Type::Closure(_)) => {
+                panic!("closures can't be returned from exported functions")
+            }
+            Some(&shared::Type::ByValue(_)) if prefix == "constructor" => {
+                format!("this.ptr = ret;")
+            }
             Some(&shared::Type::ByValue(ref name)) => {
                 dst.push_str(name);
-                if self.debug {
-                    format!("\
-                        return new {name}(ret, token);
-                    ", name = name)
-                } else {
-                    format!("\
-                        return new {name}(ret);
-                    ", name = name)
-                }
+                format!("return {}.__construct(ret);", name)
             }
             Some(&shared::Type::String) => {
                 dst.push_str("string");
@@ -269,25 +579,95 @@ impl Js {
                     m.export_name("__wbindgen_boxed_str_free"),
                 )
             }
+            Some(&shared::Type::Vector(kind)) => {
+                dst.push_str(typed_array(kind));
+                let get_array = self.expose_get_array_from_wasm(kind);
+                self.expose_wasm_exports();
+                format!("
+                    const ptr = wasm_exports.{}(ret);
+                    const len = wasm_exports.{}(ret);
+                    const realRet = {}(ptr, len);
+                    wasm_exports.{}(ret);
+                    return realRet;
+                ",
+                    m.export_name(&kind.boxed_vec_function("ptr")),
+                    m.export_name(&kind.boxed_vec_function("len")),
+                    get_array,
+                    m.export_name(&kind.boxed_vec_function("free")),
+                )
+            }
+            Some(&shared::Type::Option(ref t)) => {
+                match **t {
+                    shared::Type::Number => {
+                        dst.push_str("number | undefined");
+                        self.expose_get_global_argument(m);
+                        format!("return getGlobalArgument(0) === 0 ? undefined : ret;")
+                    }
+                    shared::Type::Boolean => {
+                        dst.push_str("boolean | undefined");
+                        format!("return ret === 2 ? undefined : ret !== 0;")
+                    }
+                    shared::Type::String => {
+                        dst.push_str("string | undefined");
+                        self.expose_get_string_from_wasm();
+                        self.expose_wasm_exports();
+                        format!("
+                            if (ret === 0)
+                                return undefined;
+                            const ptr = wasm_exports.{}(ret);
+                            const len = wasm_exports.{}(ret);
+                            const realRet = getStringFromWasm(ptr, len);
+                            wasm_exports.{}(ret);
+                            return realRet;
+                        ",
+                            m.export_name("__wbindgen_boxed_str_ptr"),
+                            m.export_name("__wbindgen_boxed_str_len"),
+                            m.export_name("__wbindgen_boxed_str_free"),
+                        )
+                    }
+                    shared::Type::JsObject => {
+                        dst.push_str("any");
+                        self.expose_take_object();
+                        format!("return ret === -1 ? undefined : takeObject(ret);")
+                    }
+                    shared::Type::ByValue(ref name) => {
+                        dst.push_str(&format!("{} | undefined", name));
+                        format!("return ret === 0 ? undefined : {}.__construct(ret);", name)
+                    }
+                    _ => panic!("unsupported type inside of `Option`"),
+                }
+            }
         };
+        dst_js.push_str(")");
+        // Setters and constructors can't have a return type annotation
+        if prefix == "set" || prefix == "constructor" {
+            dst.truncate(sig_len);
+            dst.push_str(")");
+        }
         let mut dst_ts = dst.clone();
         dst_ts.push_str(";");
+        if !self.typescript {
+            dst = dst_js;
+        }
         dst.push_str(" {\n        ");
         dst.push_str(&arg_conversions);
         self.expose_wasm_exports();
         if destructors.len() == 0 {
             dst.push_str(&format!("\
                 const ret = wasm_exports.{f}({passed});
+                {catch_err}
                 {convert_ret}
             ",
                 f = m.export_name(wasm_name),
                 passed = passed_args,
+                catch_err = catch_err,
                 convert_ret = convert_ret,
             ));
         } else {
             dst.push_str(&format!("\
                 try {{
                     const ret = wasm_exports.{f}({passed});
+                    {catch_err}
                     {convert_ret}
                 }} finally {{
                     {destructors}
@@ -295,31 +675,56 @@ impl Js {
             ",
                 f = m.export_name(wasm_name),
                 passed = passed_args,
+                catch_err = catch_err,
                 destructors = destructors,
                 convert_ret = convert_ret,
             ));
         }
         dst.push_str("}");
         self.wasm_exports_bound.insert(wasm_name.to_string());
-        (format!("{} {}", prefix, dst), dst_ts)
+        if prefix == "constructor" {
+            (dst, dst_ts)
+        } else {
+            (format!("{} {}", prefix, dst), dst_ts)
+        }
     }
 
-    pub fn generate_import(&mut self, import: &shared::Function)
+    pub fn generate_import(&mut self, import: &shared::Import, m: &Mapped)
         -> (String, String)
     {
+        let catch = import.catch;
+        let shim_name = import.shim_name();
+        let class = import.class.as_ref();
+        let method = import.method;
+        let constructor = import.constructor;
+        let global = import.global;
+        // Items from a JS module are imported under an alias at the top of
+        // the file, everything else is found on the `imports` object given to
+        // us.
+        let target = match import.module {
+            Some(ref module) => {
+                let item = class.unwrap_or(&import.function.name);
+                self.import_module_item(module, item)
+            }
+            None => {
+                format!("_imports.{}", class.unwrap_or(&import.function.name))
+            }
+        };
+        let import = &import.function;
         let mut dst = String::new();
         let mut ts_dst = String::new();
 
-        dst.push_str(&format!("function {0}_shim(", import.name));
+        dst.push_str(&format!("function {0}_shim(", shim_name));
 
         ts_dst.push_str(&import.name);
-        ts_dst.push_str("(");
+        if !global {
+            ts_dst.push_str("(");
+        }
 
-        let mut invocation = String::new();
+        let mut invocation = Vec::new();
+        let mut prelude = String::new();
+        let mut finally = String::new();
         for (i, arg) in import.arguments.iter().enumerate() {
-            if invocation.len() > 0 {
-                invocation.push_str(", ");
-            }
             if i > 0 {
                 dst.push_str(", ");
                 ts_dst.push_str(", ");
@@ -328,96 +733,508 @@ impl Js {
             match *arg {
                 shared::Type::Number => {
                     ts_dst.push_str("number");
-                    invocation.push_str(&format!("arg{}", i));
-                    dst.push_str(&format!("arg{}: number", i));
+                    invocation.push(format!("arg{}", i));
+                    dst.push_str(&format!("arg{}{}", i, self.ty("number")));
                 }
                 shared::Type::Boolean => {
                     ts_dst.push_str("boolean");
-                    invocation.push_str(&format!("arg{} != 0", i));
-                    dst.push_str(&format!("arg{}: number", i));
+                    invocation.push(format!("arg{} != 0", i));
+                    dst.push_str(&format!("arg{}{}", i, self.ty("number")));
                 }
-                shared::Type::BorrowedStr => {
+                shared::Type::BorrowedStr |
+                shared::Type::String => {
                     ts_dst.push_str("string");
                     self.expose_get_string_from_wasm();
-                    invocation.push_str(&format!("getStringFromWasm(ptr{0}, len{0})", i));
-                    dst.push_str(&format!("ptr{0}: number, len{0}: number", i));
+                    invocation.push(format!("getStringFromWasm(ptr{0}, len{0})", i));
+                    dst.push_str(&format!("ptr{0}{1}, len{0}{1}", i, self.ty("number")));
+                }
+                shared::Type::BorrowedSlice(kind) |
+                shared::Type::Vector(kind) => {
+                    ts_dst.push_str(typed_array(kind));
+                    let get_array = self.expose_get_array_from_wasm(kind);
+                    invocation.push(format!("{}(ptr{1}, len{1})", get_array, i));
+                    dst.push_str(&format!("ptr{0}{1}, len{0}{1}", i, self.ty("number")));
+                }
+                shared::Type::BorrowedMutSlice(kind) => {
+                    // Mutable slices are handed to JS as a view directly into
+                    // wasm memory, avoiding a copy. Immutable slices can't use
+                    // a view as JS could otherwise mutate them.
+                    ts_dst.push_str(typed_array(kind));
+                    self.expose_global_memory();
+                    invocation.push(format!("\
+                        new {array}(memory.buffer).subarray(ptr{i} / {size}, ptr{i} / {size} + len{i})\
+                    ", array = typed_array(kind), size = kind.size(), i = i));
+                    dst.push_str(&format!("ptr{0}{1}, len{0}{1}", i, self.ty("number")));
                 }
                 shared::Type::JsObject => {
                     ts_dst.push_str("any");
                     self.expose_take_object();
-                    invocation.push_str(&format!("takeObject(arg{})", i));
-                    dst.push_str(&format!("arg{}: number", i));
+                    invocation.push(format!("takeObject(arg{})", i));
+                    dst.push_str(&format!("arg{}{}", i, self.ty("number")));
                 }
                 shared::Type::JsObjectRef => {
                     ts_dst.push_str("any");
                     self.expose_get_object();
-                    invocation.push_str(&format!("getObject(arg{})", i));
-                    dst.push_str(&format!("arg{}: number", i));
+                    invocation.push(format!("getObject(arg{})", i));
+                    dst.push_str(&format!("arg{}{}", i, self.ty("number")));
+                }
+                shared::Type::ByValue(ref s) => {
+                    ts_dst.push_str(s);
+                    invocation.push(format!("{}.__construct(arg{})", s, i));
+                    dst.push_str(&format!("arg{}{}", i, self.ty("number")));
                 }
-                shared::Type::String |
                 shared::Type::ByRef(_) |
-                shared::Type::ByMutRef(_) |
-                shared::Type::ByValue(_) => {
+                shared::Type::ByMutRef(_) => {
                     panic!("unsupported type in import");
                 }
+                shared::Type::Option(ref t) => {
+                    match **t {
+                        shared::Type::Number => {
+                            ts_dst.push_str("number | undefined");
+                            invocation.push(format!("some{0} === 0 ? undefined : arg{0}", i));
+                            dst.push_str(&format!("some{0}{1}, arg{0}{1}", i, self.ty("number")));
+                        }
+                        shared::Type::Boolean => {
+                            ts_dst.push_str("boolean | undefined");
+                            invocation.push(format!("arg{0} === 2 ? undefined : arg{0} !== 0", i));
+                            dst.push_str(&format!("arg{}{}", i, self.ty("number")));
+                        }
+                        shared::Type::BorrowedStr |
+                        shared::Type::String => {
+                            ts_dst.push_str("string | undefined");
+                            self.expose_get_string_from_wasm();
+                            invocation.push(format!("\
+                                ptr{0} === 0 ? undefined : getStringFromWasm(ptr{0}, len{0})\
+                            ", i));
+                            dst.push_str(&format!("ptr{0}{1}, len{0}{1}", i, self.ty("number")));
+                        }
+                        shared::Type::JsObject => {
+                            ts_dst.push_str("any");
+                            self.expose_take_object();
+                            invocation.push(format!("arg{0} === -1 ? undefined : takeObject(arg{0})", i));
+                            dst.push_str(&format!("arg{}{}", i, self.ty("number")));
+                        }
+                        shared::Type::ByValue(ref s) => {
+                            ts_dst.push_str(&format!("{} | undefined", s));
+                            invocation.push(format!("\
+                                arg{i} === 0 ? undefined : {s}.__construct(arg{i})\
+                            ", i = i, s = s));
+                            dst.push_str(&format!("arg{}{}", i, self.ty("number")));
+                        }
+                        _ => panic!("unsupported type in import"),
+                    }
+                }
+                shared::Type::Result(_) => panic!("unsupported type in import"),
+                shared::Type::Closure(ref c) => {
+                    ts_dst.push_str(&self.closure_ts_type(c));
+                    let state = format!("state{}", i);
+                    let f = self.closure_function(c, &state, m);
+                    if c.owned {
+                        // The JS function is created the first time the
+                        // closure is passed to JS and then reused until
+                        // Rust drops the `Closure`.
+                        self.expose_global_memory();
+                        self.expose_add_heap_object();
+                        self.expose_get_object();
+                        prelude.push_str(&format!("
+                            const view{i} = new Uint32Array(memory.buffer);
+                            if (view{i}[idx{i} / 4] === 0xffffffff) {{
+                                const {state} = {{ a: a{i}, b: b{i} }};
+                                const cb = {f};
+                                {cb}.state = {state};
+                                view{i}[idx{i} / 4] = addHeapObject(cb);
+                            }}
+                        ", i = i, state = state, f = f, cb = self.cast("cb", "any")));
+                        invocation.push(format!("getObject(view{0}[idx{0} / 4])", i));
+                        dst.push_str(&format!("a{0}{1}, b{0}{1}, idx{0}{1}", i, self.ty("number")));
+                    } else {
+                        // Borrowed closures are only valid for the duration
+                        // of the call.
+                        prelude.push_str(&format!("
+                            const {} = {{ a: a{i}, b: b{i} }};
+                        ", state, i = i));
+                        finally.push_str(&format!("
+                            {0}.a = 0;
+                            {0}.b = 0;
+                        ", state));
+                        invocation.push(f);
+                        dst.push_str(&format!("a{0}{1}, b{0}{1}", i, self.ty("number")));
+                    }
+                }
+                shared::Type::Enum(ref e) => {
+                    ts_dst.push_str(e);
+                    invocation.push(format!("arg{}", i));
+                    dst.push_str(&format!("arg{}{}", i, self.ty("number")));
+                }
+                shared::Type::Char => {
+                    ts_dst.push_str("string");
+                    invocation.push(format!("String.fromCodePoint(arg{})", i));
+                    dst.push_str(&format!("arg{}{}", i, self.ty("number")));
+                }
+                shared::Type::I64 |
+                shared::Type::U64 => {
+                    ts_dst.push_str(self.int64_ty());
+                    let signed = match *arg {
+                        shared::Type::I64 => true,
+                        _ => false,
+                    };
+                    let from_wasm = self.expose_int64_from_wasm(signed);
+                    invocation.push(format!("{}(lo{1}, hi{1})", from_wasm, i));
+                    dst.push_str(&format!("lo{0}{1}, hi{0}{1}", i, self.ty("number")));
+                }
             }
         }
-        ts_dst.push_str("): ");
-        dst.push_str("): ");
-        let invoc = format!("_imports.{}({})", import.name, invocation);
-        let invoc = match import.ret {
-            Some(shared::Type::Number) => {
+        ts_dst.push_str(if global { ": " } else { "): " });
+        // With `catch` only the `Ok` type is passed back through the normal
+        // return value, an exception is written to `exn_data` instead.
+        let ret = match import.ret {
+            Some(shared::Type::Result(ref t)) if catch => t.as_ref().map(|t| &**t),
+            ref ret => ret.as_ref(),
+        };
+        let out_param = match ret {
+            Some(&shared::Type::String) |
+            Some(&shared::Type::Vector(_)) => Some("len_ptr"),
+            Some(&shared::Type::I64) |
+            Some(&shared::Type::U64) => Some("hi_ptr"),
+            Some(&shared::Type::Option(ref t)) => {
+                match **t {
+                    shared::Type::String => Some("len_ptr"),
+                    shared::Type::Number => Some("some_ptr"),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        let mut extra_params = Vec::new();
+        extra_params.extend(out_param);
+        if catch {
+            extra_params.push("exn_data");
+        }
+        for (i, param) in extra_params.iter().enumerate() {
+            if import.arguments.len() > 0 || i > 0 {
+                dst.push_str(", ");
+            }
+            dst.push_str(&format!("{}{}", param, self.ty("number")));
+        }
+        dst.push_str(")");
+        let invoc = match class {
+            Some(_) if constructor => {
+                format!("new {}({})", target, invocation.join(", "))
+            }
+            // The first argument is the receiver, the object the method is
+            // invoked on.
+            Some(_) if method => {
+                format!("{}.{}({})", invocation[0], import.name, invocation[1..].join(", "))
+            }
+            Some(_) => {
+                format!("{}.{}({})", target, import.name, invocation.join(", "))
+            }
+            // Statics are read rather than called
+            None if global => target,
+            None => format!("{}({})", target, invocation.join(", ")),
+        };
+        let invoc = match ret {
+            Some(&shared::Type::Number) => {
                 ts_dst.push_str("number");
-                dst.push_str("number");
-                invoc
+                dst.push_str(&self.ty("number"));
+                format!("return {};", invoc)
+            }
+            Some(&shared::Type::Enum(ref e)) => {
+                ts_dst.push_str(e);
+                dst.push_str(&self.ty("number"));
+                format!("return {};", invoc)
             }
-            Some(shared::Type::Boolean) => {
+            Some(&shared::Type::Char) => {
+                ts_dst.push_str("string");
+                dst.push_str(&self.ty("number"));
+                let assert_char = if self.debug {
+                    self.expose_assert_char();
+                    "_assertChar(ret);"
+                } else {
+                    ""
+                };
+                format!("
+                    const ret = {};
+                    {}
+                    return {};
+                ", invoc, assert_char, self.cast("ret.codePointAt(0)", "number"))
+            }
+            Some(&shared::Type::I64) |
+            Some(&shared::Type::U64) => {
+                ts_dst.push_str(self.int64_ty());
+                dst.push_str(&self.ty("number"));
+                self.expose_global_memory();
+                let to_wasm = self.expose_int64_to_wasm();
+                format!("
+                    const [lo, hi] = {}({});
+                    (new Uint32Array(memory.buffer))[hi_ptr / 4] = hi;
+                    return lo;
+                ", to_wasm, invoc)
+            }
+            Some(&shared::Type::Boolean) => {
                 ts_dst.push_str("boolean");
-                dst.push_str("number");
-                format!("{} ? 1 : 0", invoc)
+                dst.push_str(&self.ty("number"));
+                format!("return {} ? 1 : 0;", invoc)
             }
-            Some(shared::Type::JsObject) => {
+            Some(&shared::Type::JsObject) => {
                 ts_dst.push_str("any");
-                dst.push_str("number");
+                dst.push_str(&self.ty("number"));
                 self.expose_add_heap_object();
-                format!("addHeapObject({})", invoc)
+                format!("return addHeapObject({});", invoc)
+            }
+            Some(&shared::Type::String) => {
+                ts_dst.push_str("string");
+                dst.push_str(&self.ty("number"));
+                self.expose_pass_string_to_wasm(m);
+                format!("
+                    const [retptr, retlen] = passStringToWasm({});
+                    (new Uint32Array(memory.buffer))[len_ptr / 4] = retlen;
+                    return retptr;
+                ", invoc)
+            }
+            Some(&shared::Type::ByValue(ref s)) => {
+                ts_dst.push_str(s);
+                dst.push_str(&self.ty("number"));
+                let assert_class = if self.debug {
+                    self.expose_assert_class();
+                    format!("_assertClass(ret, {});", s)
+                } else {
+                    String::new()
+                };
+                format!("
+                    const ret = {};
+                    {}
+                    const ptr = ret.ptr;
+                    ret.ptr = 0;
+                    return ptr;
+                ", invoc, assert_class)
+            }
+            Some(&shared::Type::Vector(kind)) => {
+                ts_dst.push_str(typed_array(kind));
+                dst.push_str(&self.ty("number"));
+                let pass_array = self.expose_pass_array_to_wasm(kind, m);
+                format!("
+                    const [retptr, retlen] = {}({});
+                    (new Uint32Array(memory.buffer))[len_ptr / 4] = retlen;
+                    return retptr;
+                ", pass_array, invoc)
+            }
+            Some(&shared::Type::Option(ref t)) => {
+                dst.push_str(&self.ty("number"));
+                match **t {
+                    shared::Type::Number => {
+                        ts_dst.push_str("number | undefined");
+                        self.expose_global_memory();
+                        format!("
+                            const ret = {};
+                            (new Uint32Array(memory.buffer))[some_ptr / 4] = ret === undefined ? 0 : 1;
+                            return ret === undefined ? 0 : ret;
+                        ", invoc)
+                    }
+                    shared::Type::Boolean => {
+                        ts_dst.push_str("boolean | undefined");
+                        format!("
+                            const ret = {};
+                            return ret === undefined ? 2 : (ret ? 1 : 0);
+                        ", invoc)
+                    }
+                    shared::Type::String => {
+                        ts_dst.push_str("string | undefined");
+                        self.expose_pass_string_to_wasm(m);
+                        format!("
+                            const ret = {};
+                            if (ret === undefined)
+                                return 0;
+                            const [retptr, retlen] = passStringToWasm(ret);
+                            (new Uint32Array(memory.buffer))[len_ptr / 4] = retlen;
+                            return retptr;
+                        ", invoc)
+                    }
+                    shared::Type::JsObject => {
+                        ts_dst.push_str("any");
+                        self.expose_add_heap_object();
+                        format!("
+                            const ret = {};
+                            return ret === undefined ? -1 : addHeapObject(ret);
+                        ", invoc)
+                    }
+                    shared::Type::ByValue(ref s) => {
+                        ts_dst.push_str(&format!("{} | undefined", s));
+                        let assert_class = if self.debug {
+                            self.expose_assert_class();
+                            format!("_assertClass(ret, {});", s)
+                        } else {
+                            String::new()
+                        };
+                        format!("
+                            const ret = {};
+                            if (ret === undefined)
+                                return 0;
+                            {}
+                            const ptr = ret.ptr;
+                            ret.ptr = 0;
+                            return ptr;
+                        ", invoc, assert_class)
+                    }
+                    _ => panic!("unsupported type in import"),
+                }
             }
             None => {
                 ts_dst.push_str("void");
-                dst.push_str("void");
-                invoc
+                dst.push_str(&self.ty("void"));
+                format!("{};", invoc)
             }
             _ => unimplemented!(),
         };
+        let invoc = if catch {
+            self.expose_global_memory();
+            self.expose_add_heap_object();
+            format!("
+                try {{
+                    {}
+                }} catch (e) {{
+                    const view = new Uint32Array(memory.buffer);
+                    view[exn_data / 4] = 1;
+                    view[exn_data / 4 + 1] = addHeapObject(e);
+                    {}
+                }}
+            ", invoc, if ret.is_some() { "return 0;" } else { "" })
+        } else {
+            invoc
+        };
+        let invoc = if finally.len() > 0 {
+            format!("
+                {}
+                try {{
+                    {}
+                }} finally {{
+                    {}
+                }}
+            ", prelude, invoc, finally)
+        } else {
+            format!("{}{}", prelude, invoc)
+        };
         ts_dst.push_str("\n");
         dst.push_str(" {\n");
-        dst.push_str(&format!("return {};\n}}", invoc));
+        dst.push_str(&format!("{}\n}}", invoc));
 
         (dst, ts_dst)
     }
 
+    /// Returns the TypeScript function type of a closure passed to JS.
+    fn closure_ts_type(&self, c: &shared::Closure) -> String {
+        let ty = |t: Option<&shared::Type>| {
+            match t {
+                Some(&shared::Type::Number) => "number",
+                Some(&shared::Type::Boolean) => "boolean",
+                Some(&shared::Type::JsObject) => "any",
+                None => "void",
+                _ => panic!("unsupported type in closure"),
+            }
+        };
+        let args = c.function.arguments.iter()
+            .enumerate()
+            .map(|(i, t)| format!("x{}: {}", i, ty(Some(t))))
+            .collect::<Vec<_>>();
+        format!("({}) => {}", args.join(", "), ty(c.function.ret.as_ref()))
+    }
+
+    /// Generates a JS function which calls a Rust closure through its
+    /// trampoline, using the pointers stored in the `state` object.
+    ///
+    /// The closure can't be called once `state` is zeroed out, and `FnMut`
+    /// closures also can't be called recursively.
+    fn closure_function(&mut self,
+                        c: &shared::Closure,
+                        state: &str,
+                        m: &Mapped) -> String {
+        self.expose_wasm_exports();
+        let mut params = Vec::new();
+        let mut args = vec![String::from("a"), format!("{}.b", state)];
+        for (i, arg) in c.function.arguments.iter().enumerate() {
+            let name = format!("x{}", i);
+            match *arg {
+                shared::Type::Number => {
+                    params.push(format!("{}{}", name, self.ty("number")));
+                    args.push(name);
+                }
+                shared::Type::Boolean => {
+                    params.push(format!("{}{}", name, self.ty("boolean")));
+                    args.push(format!("{} ? 1 : 0", name));
+                }
+                shared::Type::JsObject => {
+                    self.expose_add_heap_object();
+                    params.push(format!("{}{}", name, self.ty("any")));
+                    args.push(format!("addHeapObject({})", name));
+                }
+                _ => panic!("unsupported type in closure"),
+            }
+        }
+        let call = format!("wasm_exports.{}({})",
+                           m.export_name(&c.function.name),
+                           args.join(", "));
+        let (ret_ty, body) = match c.function.ret {
+            Some(shared::Type::Number) => ("number", format!("return {};", call)),
+            Some(shared::Type::Boolean) => ("boolean", format!("return {} !== 0;", call)),
+            Some(shared::Type::JsObject) => {
+                self.expose_take_object();
+                ("any", format!("return takeObject({});", call))
+            }
+            None => ("void", format!("{};", call)),
+            _ => panic!("unsupported type in closure"),
+        };
+        let body = if c.mutable {
+            format!("
+                {state}.a = 0;
+                try {{
+                    {body}
+                }} finally {{
+                    if ({state}.b !== 0)
+                        {state}.a = a;
+                }}
+            ", state = state, body = body)
+        } else {
+            body
+        };
+        format!("
+            ({params}){ret_ty} => {{
+                const a = {state}.a;
+                if (a === 0)
+                    throw new Error('closure invoked recursively or after being dropped');
+                {body}
+            }}
+        ",
+            params = params.join(", "),
+            ret_ty = self.ty(ret_ty),
+            state = state,
+            body = body,
+        )
+    }
+
     pub fn to_string(&mut self, m: &Mapped, program: &shared::Program) -> String {
         if self.debug {
             self.expose_global_slab();
             self.expose_global_stack();
+            let body = format!("function(){} {{
+                if (stack.length > 0)
+                    throw new Error('stack is not empty');
+                for (let i = 0; i < slab.length; i++) {{
+                    if (typeof(slab[i]) !== 'number')
+                        throw new Error('slab is not empty');
+                }}
+            }}", self.ty("void"));
             self.exports.push(
                 (
                     "assertHeapAndStackEmpty".to_string(),
-                    "function(): void {
-                        if (stack.length > 0)
-                            throw new Error('stack is not empty');
-                        for (let i = 0; i < slab.length; i++) {
-                            if (typeof(slab[i]) !== 'number')
-                                throw new Error('slab is not empty');
-                        }
-                    }".to_string(),
+                    body,
                     "assertHeapAndStackEmpty(): void;\n".to_string(),
                 )
             );
         }
 
-        for class in self.classes.iter() {
-            self.globals.push_str(class);
+        for &(ref name, ref class) in self.classes.iter() {
+            let class = self.export(name, class);
+            self.globals.push_str(&class);
             self.globals.push_str("\n");
         }
         let wasm_exports = self.typescript_wasm_exports(&m.module);
@@ -475,23 +1292,36 @@ impl Js {
         let mut extra_imports_interface = String::new();
         let mut imports_bound = HashSet::new();
         let mut imports_interface = String::new();
+        let mut imported_classes = HashSet::new();
         for import in program.imports.iter() {
             // Only actually generate this import if it ended up being used in
             // the wasm module, an optimization pass at some point may have
             // ended up removing the code that needed the import, removing the
             // import.
-            let name = m.import_name(&import.name);
+            let name = m.import_name(&import.shim_name());
             if !wasm_imports.contains_key(name) {
                 continue
             }
             imports_bound.insert(name.to_string());
-            let (val, ts) = self.generate_import(import);
+            let (val, ts) = self.generate_import(import, m);
             imports_object.push_str(&name);
             imports_object.push_str(":");
             imports_object.push_str(&val);
             imports_object.push_str(",\n");
-            imports_interface.push_str(&ts);
-            imports_interface.push_str("\n");
+            // Functions of an imported class are all found through the class
+            // itself, which is what needs to be provided in the imports.
+            match import.class {
+                _ if import.module.is_some() => {}
+                Some(ref class) => {
+                    if imported_classes.insert(class.clone()) {
+                        imports_interface.push_str(&format!("{}: any;\n", class));
+                    }
+                }
+                None => {
+                    imports_interface.push_str(&ts);
+                    imports_interface.push_str("\n");
+                }
+            }
         }
 
         // If the user otherwise specified functions to import which *weren't*
@@ -535,6 +1365,76 @@ impl Js {
                 ", m.import_name(name), f(self)));
             };
 
+            bind("__wbindgen_cb_drop", &|me| {
+                me.expose_take_object();
+                format!("
+                    function(idx{}){} {{
+                        const state = takeObject(idx).state;
+                        state.a = 0;
+                        state.b = 0;
+                    }}
+                ", me.ty("number"), me.ty("void"))
+            });
+
+            bind("__wbindgen_call", &|me| {
+                me.expose_global_memory();
+                me.expose_get_object();
+                me.expose_add_heap_object();
+                format!("
+                    function(f{0}, this_{0}, args_ptr{0}, len{0}, exn_data{0}){0} {{
+                        let view = new Uint32Array(memory.buffer);
+                        const args{1} = [];
+                        for (let i = 0; i < len; i++)
+                            args.push(getObject(view[args_ptr / 4 + i]));
+                        const thisArg = this_ === -1 ? undefined : getObject(this_);
+                        try {{
+                            return addHeapObject(getObject(f).apply(thisArg, args));
+                        }} catch (e) {{
+                            view = new Uint32Array(memory.buffer);
+                            view[exn_data / 4] = 1;
+                            view[exn_data / 4 + 1] = addHeapObject(e);
+                            return 0;
+                        }}
+                    }}
+                ", me.ty("number"), me.ty("any[]"))
+            });
+
+            bind("__wbindgen_object_get", &|me| {
+                me.expose_get_object();
+                me.catching_intrinsic(&["i", "key"], "
+                    return addHeapObject(getObject(i)[getObject(key)]);
+                ")
+            });
+
+            bind("__wbindgen_object_set", &|me| {
+                me.expose_get_object();
+                me.catching_intrinsic(&["i", "key", "value"], "
+                    getObject(i)[getObject(key)] = getObject(value);
+                    return 0;
+                ")
+            });
+
+            bind("__wbindgen_object_has", &|me| {
+                me.expose_get_object();
+                me.catching_intrinsic(&["i", "key"], "
+                    return getObject(key) in getObject(i) ? 1 : 0;
+                ")
+            });
+
+            bind("__wbindgen_object_delete", &|me| {
+                me.expose_get_object();
+                me.catching_intrinsic(&["i", "key"], "
+                    return delete getObject(i)[getObject(key)] ? 1 : 0;
+                ")
+            });
+
+            bind("__wbindgen_object_keys", &|me| {
+                me.expose_get_object();
+                me.catching_intrinsic(&["i"], "
+                    return addHeapObject(Object.keys(getObject(i)));
+                ")
+            });
+
             bind("__wbindgen_object_clone_ref", &|me| {
                 me.expose_add_heap_object();
                 me.expose_get_object();
@@ -545,10 +1445,10 @@ impl Js {
                         val.cnt += 1;
                     ")
                 } else {
-                    String::from("(val as {cnt:number}).cnt += 1;")
+                    format!("{}.cnt += 1;", me.cast("val", "{cnt:number}"))
                 };
                 format!("
-                    function(idx: number): number {{
+                    function(idx{0}){0} {{
                         // If this object is on the stack promote it to the heap.
                         if ((idx & 1) === 1)
                             return addHeapObject(getObject(idx));
@@ -556,10 +1456,10 @@ impl Js {
                         // Otherwise if the object is on the heap just bump the
                         // refcount and move on
                         const val = slab[idx >> 1];
-                        {}
+                        {1}
                         return idx;
                     }}
-                ", bump_cnt)
+                ", me.ty("number"), bump_cnt)
             });
 
             bind("__wbindgen_object_drop_ref", &|me| {
@@ -580,15 +1480,15 @@ impl Js {
 
             bind("__wbindgen_number_get", &|me| {
                 me.expose_global_memory();
-                String::from("
-                    function(n: number, invalid: number): number {
+                format!("
+                    function(n{0}, invalid{0}){0} {{
                         let obj = getObject(n);
                         if (typeof(obj) === 'number')
                             return obj;
                         (new Uint8Array(memory.buffer))[invalid] = 1;
                         return 0;
-                    }
-                ")
+                    }}
+                ", me.ty("number"))
             });
 
             bind("__wbindgen_undefined_new", &|me| {
@@ -631,16 +1531,16 @@ impl Js {
             bind("__wbindgen_symbol_new", &|me| {
                 me.expose_get_string_from_wasm();
                 me.expose_add_heap_object();
-                String::from("(ptr, len) => {
-                    let a: Symbol;
+                format!("(ptr, len) => {{
+                    let a{};
                     console.log(ptr, len);
-                    if (ptr === 0) {
+                    if (ptr === 0) {{
                         a = Symbol();
-                    } else {
+                    }} else {{
                         a = Symbol(getStringFromWasm(ptr, len));
-                    }
+                    }}
                     return addHeapObject(a);
-                }")
+                }}", me.ty("Symbol"))
             });
 
             bind("__wbindgen_is_symbol", &|me| {
@@ -648,13 +1548,116 @@ impl Js {
                 String::from("(i) => typeof(getObject(i)) == 'symbol' ? 1 : 0")
             });
 
+            bind("__wbindgen_is_object", &|me| {
+                me.expose_get_object();
+                String::from("(i) => {
+                    const v = getObject(i);
+                    return typeof(v) === 'object' && v !== null ? 1 : 0;
+                }")
+            });
+
+            bind("__wbindgen_is_function", &|me| {
+                me.expose_get_object();
+                String::from("(i) => typeof(getObject(i)) === 'function' ? 1 : 0")
+            });
+
+            bind("__wbindgen_is_array", &|me| {
+                me.expose_get_object();
+                String::from("(i) => Array.isArray(getObject(i)) ? 1 : 0")
+            });
+
+            bind("__wbindgen_typeof", &|me| {
+                me.expose_pass_string_to_wasm(m);
+                me.expose_get_object();
+                me.expose_global_memory();
+                String::from("(i, len_ptr) => {
+                    const [ptr, len] = passStringToWasm(typeof(getObject(i)));
+                    (new Uint32Array(memory.buffer))[len_ptr / 4] = len;
+                    return ptr;
+                }")
+            });
+
+            bind("__wbindgen_instanceof", &|me| {
+                me.expose_get_object();
+                // `instanceof` throws if the right hand side isn't callable,
+                // which is reported as `false` instead of unwinding wasm.
+                format!("
+                    function(i{0}, class_{0}){0} {{
+                        try {{
+                            return getObject(i) instanceof getObject(class_) ? 1 : 0;
+                        }} catch (e) {{
+                            return 0;
+                        }}
+                    }}
+                ", me.ty("number"))
+            });
+
+            bind("__wbindgen_loose_eq", &|me| {
+                me.expose_get_object();
+                String::from("(a, b) => getObject(a) == getObject(b) ? 1 : 0")
+            });
+
+            bind("__wbindgen_strict_eq", &|me| {
+                me.expose_get_object();
+                String::from("(a, b) => getObject(a) === getObject(b) ? 1 : 0")
+            });
+
+            bind("__wbindgen_json_parse", &|me| {
+                me.expose_add_heap_object();
+                me.expose_get_string_from_wasm();
+                String::from("(ptr, len) => addHeapObject(JSON.parse(getStringFromWasm(ptr, len)))")
+            });
+
+            bind("__wbindgen_json_serialize", &|me| {
+                me.expose_pass_string_to_wasm(m);
+                me.expose_get_object();
+                // `JSON.stringify` throws on cycles and `BigInt` values and
+                // returns `undefined` for functions and symbols, all of which
+                // are handed to Rust as exceptions. A plain `undefined` is
+                // serialized as `null` though.
+                me.catching_intrinsic(&["idx", "len_ptr"], "
+                    const val = getObject(idx);
+                    const s = val === undefined ? 'null' : JSON.stringify(val);
+                    if (s === undefined)
+                        throw new TypeError(`a ${typeof(val)} can't be serialized as JSON`);
+                    const [ptr, len] = passStringToWasm(s);
+                    (new Uint32Array(memory.buffer))[len_ptr / 4] = len;
+                    return ptr;
+                ")
+            });
+
+            bind("__wbindgen_array_new", &|me| {
+                me.expose_add_heap_object();
+                String::from("() => addHeapObject([])")
+            });
+
+            bind("__wbindgen_array_push", &|me| {
+                me.expose_get_object();
+                format!("
+                    function(idx{0}, value{0}){1} {{
+                        getObject(idx).push(getObject(value));
+                    }}
+                ", me.ty("number"), me.ty("void"))
+            });
+
+            bind("__wbindgen_array_length", &|me| {
+                me.expose_get_object();
+                String::from("(idx) => getObject(idx).length")
+            });
+
+            bind("__wbindgen_array_get", &|me| {
+                me.expose_get_object();
+                me.expose_add_heap_object();
+                String::from("(idx, i) => addHeapObject(getObject(idx)[i])")
+            });
+
             bind("__wbindgen_throw", &|me| {
                 me.expose_get_string_from_wasm();
-                String::from("
-                    function(ptr: number, len: number) {
+                format!("
+                    function(ptr{0}, len{0}) {{
                         throw new Error(getStringFromWasm(ptr, len));
-                    }
-                ")
+                    }}
+                ", me.ty("number"))
             });
 
             bind("__wbindgen_string_get", &|me| {
@@ -679,21 +1682,54 @@ impl Js {
         if self.exposed_globals.contains(&"wasm_exports") {
             writes.push_str("wasm_exports = exports;\n");
         }
-        format!("
-            /* tslint:disable */
-            {globals}
+        let interfaces = if self.typescript {
+            format!("
+                interface WasmImportsTop {{
+                    env: WasmImports,
+                }}
 
-            interface WasmImportsTop {{
-                env: WasmImports,
-            }}
+                interface WasmImports {{
+                    {wasm_imports}
+                }}
 
-            interface WasmImports {{
-                {wasm_imports}
-            }}
+                interface WasmExports {{
+                    {wasm_exports}
+                }}
 
-            interface WasmExports {{
-                {wasm_exports}
-            }}
+                export interface Imports {{
+                    {imports_interface}
+                }}
+
+                {extra_imports_interface}
+
+                export interface Exports {{
+                    module: WebAssembly.Module;
+                    instance: WebAssembly.Module;
+                    {exports_interface}
+                }}
+
+                {extra_exports_interface}
+            ",
+                imports_interface = imports_interface,
+                extra_imports_interface = extra_imports_interface,
+                exports_interface = exports_interface,
+                extra_exports_interface = extra_exports_interface,
+                wasm_imports = wasm_imports.values()
+                    .map(|s| &**s)
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                wasm_exports = wasm_exports.values()
+                    .map(|s| &**s)
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )
+        } else {
+            String::new()
+        };
+
+        self.declarations = format!("
+            /* tslint:disable */
+            {classes}
 
             export interface Imports {{
                 {imports_interface}
@@ -709,14 +1745,18 @@ impl Js {
 
             {extra_exports_interface}
 
-            function xform(obj: WebAssembly.ResultObject): Exports {{
-                let {{ module, instance }} = obj;
-                let exports: WasmExports = instance.exports;
-                {writes}
-                return {exports};
-            }}
-            export function instantiate(bytes: any, _imports: Imports): Promise<Exports> {{
-                let wasm_imports: WasmImportsTop = {{
+            export declare function instantiate(bytes: any, _imports: Imports): Promise<Exports>;
+        ",
+            classes = self.class_declarations.join("\n"),
+            imports_interface = imports_interface,
+            extra_imports_interface = extra_imports_interface,
+            exports_interface = exports_interface,
+            extra_exports_interface = extra_exports_interface,
+        );
+
+        let instantiate = format!("
+            function instantiate(bytes{any}, _imports{imports}){ret} {{
+                let wasm_imports{wasm_imports} = {{
                     env: {{
                         {imports_object}
                     }},
@@ -724,22 +1764,61 @@ impl Js {
                 return WebAssembly.instantiate(bytes, wasm_imports).then(xform);
             }}
         ",
+            any = self.ty("any"),
+            imports = self.ty("Imports"),
+            ret = self.ty("Promise<Exports>"),
+            wasm_imports = self.ty("WasmImportsTop"),
+            imports_object = imports_object,
+        );
+
+        let mut module_imports = String::new();
+        let require = self.nodejs && !self.typescript;
+        for (module, &(idx, ref items)) in self.module_imports.iter() {
+            let items = items.iter()
+                .map(|item| {
+                    let alias = module_item_alias(idx, item);
+                    if require {
+                        format!("{}: {}", item, alias)
+                    } else {
+                        format!("{} as {}", item, alias)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            if require {
+                module_imports.push_str(&format!("const {{ {} }} = require(\"{}\");\n",
+                                                 items, module));
+            } else {
+                module_imports.push_str(&format!("import {{ {} }} from \"{}\";\n",
+                                                 items, module));
+            }
+        }
+
+        format!("
+            {header}
+            {module_imports}
+            {globals}
+
+            {interfaces}
+
+            function xform(obj{result_object}){exports_ty} {{
+                let {{ module, instance }} = obj;
+                let exports{wasm_exports_ty} = instance.exports;
+                {writes}
+                return {exports};
+            }}
+            {instantiate}
+        ",
+            header = if self.typescript { "/* tslint:disable */" } else { "" },
+            module_imports = module_imports,
             globals = self.globals,
+            interfaces = interfaces,
+            result_object = self.ty("WebAssembly.ResultObject"),
+            exports_ty = self.ty("Exports"),
+            wasm_exports_ty = self.ty("WasmExports"),
             exports = exports,
-            imports_object = imports_object,
             writes = writes,
-            imports_interface = imports_interface,
-            extra_imports_interface = extra_imports_interface,
-            exports_interface = exports_interface,
-            extra_exports_interface = extra_exports_interface,
-            wasm_imports = wasm_imports.values()
-                .map(|s| &**s)
-                .collect::<Vec<_>>()
-                .join("\n"),
-            wasm_exports = wasm_exports.values()
-                .map(|s| &**s)
-                .collect::<Vec<_>>()
-                .join("\n"),
+            instantiate = self.export("instantiate", &instantiate),
         )
     }
 
@@ -890,14 +1969,14 @@ impl Js {
                     return;
             ")
         } else {
-            String::from("
-                (obj as {cnt:number}).cnt -= 1;
-                if ((obj as {cnt:number}).cnt > 0)
+            format!("
+                {0}.cnt -= 1;
+                if ({0}.cnt > 0)
                     return;
-            ")
+            ", self.cast("obj", "{cnt:number}"))
         };
         self.globals.push_str(&format!("
-            function dropRef(idx: number): void {{
+            function dropRef(idx{}){} {{
                 {}
 
                 let obj = slab[idx >> 1];
@@ -907,34 +1986,34 @@ impl Js {
                 slab[idx >> 1] = slab_next;
                 slab_next = idx >> 1;
             }}
-        ", validate_owned, dec_ref));
+        ", self.ty("number"), self.ty("void"), validate_owned, dec_ref));
     }
 
     fn expose_global_stack(&mut self) {
         if !self.exposed_globals.insert("stack") {
             return
         }
-        self.globals.push_str("
-            let stack: any[] = [];
-        ");
+        self.globals.push_str(&format!("
+            let stack{} = [];
+        ", self.ty("any[]")));
     }
 
     fn expose_global_slab(&mut self) {
         if !self.exposed_globals.insert("slab") {
             return
         }
-        self.globals.push_str("
-            let slab: ({ obj: any, cnt: number } | number)[] = [];
-        ");
+        self.globals.push_str(&format!("
+            let slab{} = [];
+        ", self.ty("({ obj: any, cnt: number } | number)[]")));
     }
 
     fn expose_global_slab_next(&mut self) {
         if !self.exposed_globals.insert("slab_next") {
             return
         }
-        self.globals.push_str("
-            let slab_next: number = 0;
-        ");
+        self.globals.push_str(&format!("
+            let slab_next{} = 0;
+        ", self.ty("number")));
     }
 
     fn expose_get_object(&mut self) {
@@ -951,12 +2030,12 @@ impl Js {
                 return val.obj;
             ")
         } else {
-            String::from("
-                return (val as {obj:any}).obj;
-            ")
+            format!("
+                return {}.obj;
+            ", self.cast("val", "{obj:any}"))
         };
         self.globals.push_str(&format!("
-            function getObject(idx: number): any {{
+            function getObject(idx{}){} {{
                 if ((idx & 1) === 1) {{
                     return stack[idx >> 1];
                 }} else {{
@@ -964,58 +2043,146 @@ impl Js {
                     {}
                 }}
             }}
-        ", get_obj));
+        ", self.ty("number"), self.ty("any"), get_obj));
     }
 
     fn expose_global_memory(&mut self) {
         if !self.exposed_globals.insert("memory") {
             return
         }
-        self.globals.push_str("let memory: WebAssembly.Memory;\n");
+        let memory = format!("let memory{};\n", self.ty("WebAssembly.Memory"));
+        self.globals.push_str(&memory);
     }
 
     fn expose_wasm_exports(&mut self) {
         if !self.exposed_globals.insert("wasm_exports") {
             return
         }
-        self.globals.push_str("let wasm_exports: WasmExports;\n");
-    }
-
-    fn expose_check_token(&mut self) {
-        if !self.exposed_globals.insert("check_token") {
-            return
-        }
-        self.globals.push_str("\
-            const token = Symbol('foo');
-            function _checkToken(sym: Symbol): void {
-                if (token !== sym)
-                    throw new Error('cannot invoke `new` directly');
-            }
-        ");
+        let wasm_exports = format!("let wasm_exports{};\n", self.ty("WasmExports"));
+        self.globals.push_str(&wasm_exports);
     }
 
     fn expose_assert_num(&mut self) {
         if !self.exposed_globals.insert("assert_num") {
             return
         }
-        self.globals.push_str("\
-            function _assertNum(n: number): void {
+        let assert_num = format!("\
+            function _assertNum(n{}){} {{
                 if (typeof(n) !== 'number')
                     throw new Error('expected a number argument');
-            }
-        ");
+            }}
+        ", self.ty("number"), self.ty("void"));
+        self.globals.push_str(&assert_num);
+    }
+
+    fn expose_assert_char(&mut self) {
+        if !self.exposed_globals.insert("assert_char") {
+            return
+        }
+        let assert_char = format!("\
+            function _assertChar(c{}) {{
+                if (typeof(c) !== 'string' || Array.from(c).length !== 1)
+                    throw new Error('expected a single character');
+            }}
+        ", self.ty("string"));
+        self.globals.push_str(&assert_char);
+    }
+
+    /// Exposes `charToWasm`, which passes the first code point of a string to
+    /// wasm. Anything without one is mapped to a value which isn't a valid
+    /// `char`, so Rust rejects it even when `_assertChar` isn't used.
+    fn expose_char_to_wasm(&mut self) {
+        if !self.exposed_globals.insert("char_to_wasm") {
+            return
+        }
+        let char_to_wasm = format!("\
+            function charToWasm(c{}){} {{
+                const n = typeof(c) === 'string' ? c.codePointAt(0) : undefined;
+                return n === undefined ? 0xffffffff : n;
+            }}
+        ", self.ty("any"), self.ty("number"));
+        self.globals.push_str(&char_to_wasm);
+    }
+
+    /// The JS type 64-bit integers show up as, either a lossless `bigint` or
+    /// a `number` which is only exact up to 2^53.
+    fn int64_ty(&self) -> &'static str {
+        if self.bigint {
+            "bigint"
+        } else {
+            "number"
+        }
+    }
+
+    /// Exposes a function combining the two `u32` halves of a 64-bit integer
+    /// into a JS value, returning the name of the function.
+    fn expose_int64_from_wasm(&mut self, signed: bool) -> &'static str {
+        let name = if signed { "i64FromWasm" } else { "u64FromWasm" };
+        if !self.exposed_globals.insert(name) {
+            return name
+        }
+        let body = match (self.bigint, signed) {
+            (true, true) => "\
+                return BigInt.asIntN(64, (BigInt(hi >>> 0) << BigInt(32)) | BigInt(lo >>> 0));
+            ",
+            (true, false) => "\
+                return (BigInt(hi >>> 0) << BigInt(32)) | BigInt(lo >>> 0);
+            ",
+            (false, true) => "\
+                return (hi | 0) * 4294967296 + (lo >>> 0);
+            ",
+            (false, false) => "\
+                return (hi >>> 0) * 4294967296 + (lo >>> 0);
+            ",
+        };
+        self.globals.push_str(&format!("
+            function {name}(lo{num}, hi{num}){ret} {{
+                {body}
+            }}
+        ", name = name, num = self.ty("number"), ret = self.ty(self.int64_ty()),
+           body = body));
+        name
+    }
+
+    /// Exposes a function splitting a 64-bit integer, signed or not, into the
+    /// `u32` halves passed to wasm, returning the name of the function.
+    fn expose_int64_to_wasm(&mut self) -> &'static str {
+        let name = "int64ToWasm";
+        if !self.exposed_globals.insert(name) {
+            return name
+        }
+        let body = if self.bigint {
+            "\
+                const v = BigInt.asUintN(64, BigInt(n));
+                return [Number(v & BigInt(0xffffffff)), Number(v >> BigInt(32))];
+            "
+        } else {
+            "\
+                if (!Number.isInteger(n))
+                    throw new Error('expected an integer argument');
+                return [n % 4294967296, Math.floor(n / 4294967296)];
+            "
+        };
+        self.globals.push_str(&format!("
+            function {name}(n{arg}){ret} {{
+                {body}
+            }}
+        ", name = name, arg = self.ty(self.int64_ty()), ret = self.ty("[number, number]"),
+           body = body));
+        name
     }
 
     fn expose_assert_bool(&mut self) {
         if !self.exposed_globals.insert("assert_bool") {
             return
         }
-        self.globals.push_str("\
-            function _assertBoolean(n: boolean) {
+        let assert_bool = format!("\
+            function _assertBoolean(n{}) {{
                 if (typeof(n) !== 'boolean')
                     throw new Error('expected a boolean argument');
-            }
-        ");
+            }}
+        ", self.ty("boolean"));
+        self.globals.push_str(&assert_bool);
     }
 
     fn expose_pass_string_to_wasm(&mut self, m: &Mapped) {
@@ -1024,9 +2191,12 @@ impl Js {
         }
         self.expose_wasm_exports();
         self.expose_global_memory();
+        let sig = format!("passStringToWasm(arg{}){}",
+                          self.ty("string"),
+                          self.ty("[number, number]"));
         if self.nodejs {
             self.globals.push_str(&format!("
-                function passStringToWasm(arg: string): [number, number] {{
+                function {} {{
                     if (typeof(arg) !== 'string')
                         throw new Error('expected a string argument');
                     const buf = Buffer.from(arg);
@@ -1035,10 +2205,10 @@ impl Js {
                     buf.copy(Buffer.from(memory.buffer), ptr);
                     return [ptr, len];
                 }}
-            ", m.export_name("__wbindgen_malloc")));
+            ", sig, m.export_name("__wbindgen_malloc")));
         } else {
             self.globals.push_str(&format!("
-                function passStringToWasm(arg: string): [number, number] {{
+                function {} {{
                     if (typeof(arg) !== 'string')
                         throw new Error('expected a string argument');
                     const buf = new TextEncoder('utf-8').encode(arg);
@@ -1048,33 +2218,125 @@ impl Js {
                     array.set(buf, ptr);
                     return [ptr, len];
                 }}
-            ", m.export_name("__wbindgen_malloc")));
+            ", sig, m.export_name("__wbindgen_malloc")));
+        }
+    }
+
+    /// Exposes a function copying a typed array of `kind` into wasm memory,
+    /// returning the name of the function.
+    fn expose_pass_array_to_wasm(&mut self, kind: shared::VectorKind, m: &Mapped)
+        -> &'static str
+    {
+        let name = match kind {
+            shared::VectorKind::I8 => "passArrayI8ToWasm",
+            shared::VectorKind::U8 => "passArrayU8ToWasm",
+            shared::VectorKind::I16 => "passArrayI16ToWasm",
+            shared::VectorKind::U16 => "passArrayU16ToWasm",
+            shared::VectorKind::I32 => "passArrayI32ToWasm",
+            shared::VectorKind::U32 => "passArrayU32ToWasm",
+            shared::VectorKind::F32 => "passArrayF32ToWasm",
+            shared::VectorKind::F64 => "passArrayF64ToWasm",
+        };
+        if !self.exposed_globals.insert(name) {
+            return name
+        }
+        self.expose_wasm_exports();
+        self.expose_global_memory();
+        self.globals.push_str(&format!("
+            function {name}(arg{arg_ty}){ret_ty} {{
+                if (!(arg instanceof {array}))
+                    throw new Error('expected a {array} argument');
+                const ptr = wasm_exports.{malloc}(arg.length);
+                (new {array}(memory.buffer)).set(arg, ptr / {size});
+                return [ptr, arg.length];
+            }}
+        ",
+            name = name,
+            arg_ty = self.ty(typed_array(kind)),
+            ret_ty = self.ty("[number, number]"),
+            array = typed_array(kind),
+            size = kind.size(),
+            malloc = m.export_name(&kind.malloc_function()),
+        ));
+        name
+    }
+
+    /// Exposes a function copying `len` elements of `kind` out of wasm memory
+    /// into a new typed array, returning the name of the function.
+    fn expose_get_array_from_wasm(&mut self, kind: shared::VectorKind)
+        -> &'static str
+    {
+        let name = match kind {
+            shared::VectorKind::I8 => "getArrayI8FromWasm",
+            shared::VectorKind::U8 => "getArrayU8FromWasm",
+            shared::VectorKind::I16 => "getArrayI16FromWasm",
+            shared::VectorKind::U16 => "getArrayU16FromWasm",
+            shared::VectorKind::I32 => "getArrayI32FromWasm",
+            shared::VectorKind::U32 => "getArrayU32FromWasm",
+            shared::VectorKind::F32 => "getArrayF32FromWasm",
+            shared::VectorKind::F64 => "getArrayF64FromWasm",
+        };
+        if !self.exposed_globals.insert(name) {
+            return name
         }
+        self.expose_global_memory();
+        self.globals.push_str(&format!("
+            function {name}(ptr{num}, len{num}){ret_ty} {{
+                return (new {array}(memory.buffer)).slice(ptr / {size}, ptr / {size} + len);
+            }}
+        ",
+            name = name,
+            num = self.ty("number"),
+            ret_ty = self.ty(typed_array(kind)),
+            array = typed_array(kind),
+            size = kind.size(),
+        ));
+        name
+    }
+
+    fn expose_get_global_argument(&mut self, m: &Mapped) {
+        if !self.exposed_globals.insert("get_global_argument") {
+            return
+        }
+        self.expose_wasm_exports();
+        self.expose_global_memory();
+        self.globals.push_str(&format!("
+            function getGlobalArgument(arg{num}){num} {{
+                const idx = wasm_exports.{ptr}() / 4 + arg;
+                return (new Uint32Array(memory.buffer))[idx];
+            }}
+        ",
+            num = self.ty("number"),
+            ptr = m.export_name("__wbindgen_global_argument_ptr"),
+        ));
     }
 
     fn expose_get_string_from_wasm(&mut self) {
         if !self.exposed_globals.insert("get_string_from_wasm") {
             return
         }
+        let sig = format!("getStringFromWasm(ptr{0}, len{0}){1}",
+                          self.ty("number"),
+                          self.ty("string"));
         if self.nodejs {
             self.expose_global_memory();
-            self.globals.push_str("
-                function getStringFromWasm(ptr: number, len: number): string {
+            self.globals.push_str(&format!("
+                function {} {{
                     const buf = Buffer.from(memory.buffer).slice(ptr, ptr + len);
                     const ret = buf.toString();
                     return ret;
-                }
-            ");
+                }}
+            ", sig));
         } else {
             self.expose_global_memory();
-            self.globals.push_str("
-                function getStringFromWasm(ptr: number, len: number): string {
+            self.globals.push_str(&format!("
+                function {} {{
                     const mem = new Uint8Array(memory.buffer);
                     const slice = mem.slice(ptr, ptr + len);
                     const ret = new TextDecoder('utf-8').decode(slice);
                     return ret;
-                }
-            ");
+                }}
+            ", sig));
         }
     }
 
@@ -1082,13 +2344,13 @@ impl Js {
         if !self.exposed_globals.insert("assert_class") {
             return
         }
-        self.globals.push_str("
-            function _assertClass(instance: any, klass: any) {
+        self.globals.push_str(&format!("
+            function _assertClass(instance{0}, klass{0}) {{
                 if (!(instance instanceof klass))
-                    throw new Error(`expected instance of ${klass.name}`);
+                    throw new Error(`expected instance of ${{klass.name}}`);
                 return instance.ptr;
-            }
-        ");
+            }}
+        ", self.ty("any")));
     }
 
     fn expose_borrowed_objects(&mut self) {
@@ -1096,12 +2358,12 @@ impl Js {
             return
         }
         self.expose_global_stack();
-        self.globals.push_str("
-            function addBorrowedObject(obj: any): number {
+        self.globals.push_str(&format!("
+            function addBorrowedObject(obj{}){} {{
                 stack.push(obj);
                 return ((stack.length - 1) << 1) | 1;
-            }
-        ");
+            }}
+        ", self.ty("any"), self.ty("number")));
     }
 
     fn expose_take_object(&mut self) {
@@ -1110,13 +2372,13 @@ impl Js {
         }
         self.expose_get_object();
         self.expose_drop_ref();
-        self.globals.push_str("
-            function takeObject(idx: number): any {
+        self.globals.push_str(&format!("
+            function takeObject(idx{}){} {{
                 const ret = getObject(idx);
                 dropRef(idx);
                 return ret;
-            }
-        ");
+            }}
+        ", self.ty("number"), self.ty("any")));
     }
 
     fn expose_add_heap_object(&mut self) {
@@ -1132,12 +2394,12 @@ impl Js {
                 slab_next = next;
             ")
         } else {
-            String::from("
-                slab_next = next as number;
-            ")
+            format!("
+                slab_next = {};
+            ", self.cast("next", "number"))
         };
         self.globals.push_str(&format!("
-            function addHeapObject(obj: any): number {{
+            function addHeapObject(obj{}){} {{
                 if (slab_next == slab.length)
                     slab.push(slab.length + 1);
                 const idx = slab_next;
@@ -1146,6 +2408,94 @@ impl Js {
                 slab[idx] = {{ obj, cnt: 1 }};
                 return idx << 1;
             }}
-        ", set_slab_next));
+        ", self.ty("any"), self.ty("number"), set_slab_next));
+    }
+
+    /// Returns a `: ty` annotation when generating TypeScript, or nothing when
+    /// generating plain JS.
+    fn ty(&self, ty: &str) -> String {
+        if self.typescript {
+            format!(": {}", ty)
+        } else {
+            String::new()
+        }
+    }
+
+    /// Returns `(expr as ty)` when generating TypeScript, or just `expr` when
+    /// generating plain JS.
+    fn cast(&self, expr: &str, ty: &str) -> String {
+        if self.typescript {
+            format!("({} as {})", expr, ty)
+        } else {
+            expr.to_string()
+        }
+    }
+
+    /// Exports the item `item`, named `name`, from the generated module.
+    ///
+    /// Node doesn't understand ES2015 modules yet, so plain JS generated for
+    /// Node assigns to `exports` instead.
+    fn export(&self, name: &str, item: &str) -> String {
+        if self.typescript || !self.nodejs {
+            format!("export {}", item)
+        } else {
+            format!("{}\nexports.{} = {};\n", item, name, name)
+        }
+    }
+
+    /// Generates an intrinsic taking `params` and running `body`, which
+    /// catches any exception thrown and hands it to Rust through a trailing
+    /// `exn_data` out-param, just like `__wbindgen_call`.
+    fn catching_intrinsic(&mut self, params: &[&str], body: &str) -> String {
+        self.expose_global_memory();
+        self.expose_add_heap_object();
+        let params = params.iter()
+            .chain(Some(&"exn_data"))
+            .map(|p| format!("{}{}", p, self.ty("number")))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("
+            function({params}){number} {{
+                try {{
+                    {body}
+                }} catch (e) {{
+                    const view = new Uint32Array(memory.buffer);
+                    view[exn_data / 4] = 1;
+                    view[exn_data / 4 + 1] = addHeapObject(e);
+                    return 0;
+                }}
+            }}
+        ", params = params, number = self.ty("number"), body = body.trim())
+    }
+
+    /// Records that `item` is imported from the JS module `module`, returning
+    /// the name it's available under in the generated file.
+    ///
+    /// Items are aliased so two modules exporting the same name, or an item
+    /// named like one of our own helpers, can't collide.
+    fn import_module_item(&mut self, module: &str, item: &str) -> String {
+        let next = self.module_imports.len();
+        let &mut (idx, ref mut items) = self.module_imports
+            .entry(module.to_string())
+            .or_insert_with(|| (next, BTreeSet::new()));
+        items.insert(item.to_string());
+        module_item_alias(idx, item)
+    }
+}
+
+fn module_item_alias(module_idx: usize, item: &str) -> String {
+    format!("__wbg_mod{}_{}", module_idx, item)
+}
+
+fn typed_array(kind: shared::VectorKind) -> &'static str {
+    match kind {
+        shared::VectorKind::I8 => "Int8Array",
+        shared::VectorKind::U8 => "Uint8Array",
+        shared::VectorKind::I16 => "Int16Array",
+        shared::VectorKind::U16 => "Uint16Array",
+        shared::VectorKind::I32 => "Int32Array",
+        shared::VectorKind::U32 => "Uint32Array",
+        shared::VectorKind::F32 => "Float32Array",
+        shared::VectorKind::F64 => "Float64Array",
     }
 }
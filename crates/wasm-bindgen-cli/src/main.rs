@@ -18,17 +18,23 @@ Usage:
 Options:
     -h --help               Show this screen.
     --output-ts FILE        Output TypeScript file
+    --output-js FILE        Output JS file
+    --output-dts FILE       Output TypeScript declarations file
     --output-wasm FILE      Output WASM file
     --nodejs                Generate output for node.js, not the browser
     --debug                 Include otherwise-extraneous debug checks in output
+    --bigint                Pass 64-bit integers to JS as `BigInt`, not `number`
 ";
 
 #[derive(Debug, Deserialize)]
 struct Args {
     flag_output_ts: Option<PathBuf>,
+    flag_output_js: Option<PathBuf>,
+    flag_output_dts: Option<PathBuf>,
     flag_output_wasm: Option<PathBuf>,
     flag_nodejs: bool,
     flag_debug: bool,
+    flag_bigint: bool,
     arg_input: PathBuf,
 }
 
@@ -41,11 +47,19 @@ fn main() {
     b.input_path(&args.arg_input)
      .nodejs(args.flag_nodejs)
      .debug(args.flag_debug)
+     .bigint(args.flag_bigint)
      .uglify_wasm_names(!args.flag_debug);
     let ret = b.generate().expect("failed to generate bindings");
     if let Some(ref ts) = args.flag_output_ts {
         ret.write_ts_to(ts).expect("failed to write TypeScript output file");
-    } else {
+    }
+    if let Some(ref js) = args.flag_output_js {
+        ret.write_js_to(js).expect("failed to write JS output file");
+    }
+    if let Some(ref dts) = args.flag_output_dts {
+        ret.write_dts_to(dts).expect("failed to write TypeScript declarations file");
+    }
+    if args.flag_output_ts.is_none() && args.flag_output_js.is_none() {
         println!("{}", ret.generate_ts());
     }
     if let Some(ref wasm) = args.flag_output_wasm {
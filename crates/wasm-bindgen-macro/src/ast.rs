@@ -6,6 +6,9 @@ pub struct Program {
     pub structs: Vec<Struct>,
     pub free_functions: Vec<Function>,
     pub imports: Vec<Import>,
+    pub enums: Vec<Enum>,
+    pub consts: Vec<Const>,
+    pub imported_types: Vec<ImportedType>,
 }
 
 pub struct Function {
@@ -20,12 +23,38 @@ pub struct Import {
     pub ident: syn::Ident,
     pub vis: syn::Visibility,
     pub attrs: Vec<syn::Attribute>,
+    pub catch: bool,
+    pub class: Option<syn::Ident>,
+    pub method: bool,
+    pub constructor: bool,
+    pub global: Option<Global>,
+    pub module: Option<String>,
 }
 
+/// A `static` in a foreign module, which is read through a generated getter
+/// function the first time it's accessed.
+pub struct Global {
+    pub name: syn::Ident,
+    pub vis: syn::Visibility,
+    pub ty: Box<syn::Type>,
+}
+
+pub struct ImportedType {
+    pub name: syn::Ident,
+    pub vis: syn::Visibility,
+    pub attrs: Vec<syn::Attribute>,
+}
+
+#[derive(Clone)]
 pub enum Type {
     Integer(syn::Ident),
+    Integer64(syn::Ident),
+    Char,
     BorrowedStr,
     String,
+    BorrowedSlice(syn::Ident),
+    BorrowedMutSlice(syn::Ident),
+    Vector(syn::Ident),
     ByValue(syn::Ident),
     ByRef(syn::Ident),
     ByMutRef(syn::Ident),
@@ -34,16 +63,58 @@ pub enum Type {
     JsObject,
     JsObjectRef,
     Boolean,
+    Option(Box<Type>),
+    Result(Option<Box<Type>>),
+    Enum(syn::Ident),
+    ImportedType(syn::Ident),
+    ImportedTypeRef(syn::Ident),
+    Closure(Box<Closure>),
+}
+
+/// A closure passed to an imported function, either borrowed for the duration
+/// of the call (`&Fn(..)` and `&mut FnMut(..)`) or through a `&Closure<..>`
+/// which JS may hold on to until it's dropped in Rust.
+#[derive(Clone)]
+pub struct Closure {
+    pub mutable: bool,
+    pub owned: bool,
+    pub arguments: Vec<Type>,
+    pub ret: Option<Type>,
+    /// The `Fn(..)` or `FnMut(..)` trait object type itself
+    pub ty: syn::Type,
 }
 
 pub struct Struct {
     pub name: syn::Ident,
     pub methods: Vec<Method>,
     pub functions: Vec<Function>,
+    pub fields: Vec<StructField>,
+    pub constructor: Option<syn::Ident>,
+}
+
+pub struct StructField {
+    pub name: syn::Ident,
+    pub ty: Type,
+}
+
+pub struct Enum {
+    pub name: syn::Ident,
+    pub variants: Vec<Variant>,
+}
+
+pub struct Variant {
+    pub name: syn::Ident,
+    pub value: i32,
+}
+
+pub struct Const {
+    pub name: syn::Ident,
+    pub value: shared::ConstValue,
 }
 
 pub struct Method {
     pub mutable: bool,
+    pub consume: bool,
     pub function: Function,
 }
 
@@ -79,23 +150,117 @@ impl Program {
             Some(ref l) if l.value() == "JS" => {}
             _ => panic!("only foreign mods with the `JS` ABI are allowed"),
         }
+        let module = BindgenAttrs::find(&f.attrs).module();
         for item in f.items.iter() {
-            self.push_foreign_item(item);
+            self.push_foreign_item(item, module.as_ref());
         }
     }
 
-    pub fn push_foreign_item(&mut self, f: &syn::ForeignItem) {
+    pub fn push_foreign_item(&mut self, f: &syn::ForeignItem, module: Option<&String>) {
         let f = match *f {
             syn::ForeignItem::Fn(ref f) => f,
-            _ => panic!("only foreign functions allowed for now, not statics"),
+            syn::ForeignItem::Type(ref t) => {
+                if self.imported_types.iter().any(|a| a.name == t.ident) {
+                    panic!("redefinition of imported type: {}", t.ident);
+                }
+                let mut attrs = t.attrs.clone();
+                BindgenAttrs::strip(&mut attrs);
+                self.imported_types.push(ImportedType {
+                    name: t.ident,
+                    vis: t.vis.clone(),
+                    attrs,
+                });
+                return
+            }
+            syn::ForeignItem::Static(ref s) => return self.push_foreign_static(s, module),
+            _ => panic!("only foreign functions, types, and statics are allowed"),
         };
 
+        let opts = BindgenAttrs::find(&f.attrs);
+        let function = Function::from_decl(f.ident, &f.decl);
+
+        let method = opts.method();
+        let constructor = opts.constructor();
+        let static_method_of = opts.static_method_of();
+        if (method as u32) + (constructor as u32) + (static_method_of.is_some() as u32) > 1 {
+            panic!("only one of `method`, `constructor`, and `static_method_of` \
+                    can be specified");
+        }
+        let class = if method {
+            // The receiver is passed explicitly as the first argument, which
+            // becomes `&self` in the generated method.
+            match function.arguments.get(0) {
+                Some(&Type::ByRef(class)) => Some(class),
+                _ => panic!("imported methods must take `&T` as their first \
+                             argument, where `T` is an imported type"),
+            }
+        } else if constructor {
+            let ret = match function.ret {
+                Some(Type::Result(Some(ref t))) if opts.catch() => Some(&**t),
+                ref t => t.as_ref(),
+            };
+            match ret {
+                Some(&Type::ByValue(class)) => Some(class),
+                _ => panic!("imported constructors must return an imported type"),
+            }
+        } else {
+            static_method_of.map(|s| syn::Ident::from(s))
+        };
+
+        let mut attrs = f.attrs.clone();
+        BindgenAttrs::strip(&mut attrs);
+
         self.imports.push(Import {
-            attrs: f.attrs.clone(),
+            attrs,
             vis: f.vis.clone(),
             decl: f.decl.clone(),
             ident: f.ident.clone(),
-            function: Function::from_decl(f.ident, &f.decl),
+            function,
+            catch: opts.catch(),
+            class,
+            method,
+            constructor,
+            global: None,
+            module: module.cloned(),
+        });
+    }
+
+    fn push_foreign_static(&mut self, s: &syn::ForeignItemStatic, module: Option<&String>) {
+        if s.mutability.is_some() {
+            panic!("imported statics can't be mutable");
+        }
+        if BindgenAttrs::find(&s.attrs).catch() {
+            panic!("imported statics can't be marked with `catch`");
+        }
+
+        // Statics are read through a getter function taking no arguments
+        let decl = Box::new(syn::FnDecl {
+            fn_token: Default::default(),
+            generics: Default::default(),
+            paren_token: Default::default(),
+            inputs: Default::default(),
+            variadic: None,
+            output: syn::ReturnType::Type(Default::default(), s.ty.clone()),
+        });
+        let ident = syn::Ident::from(format!("__wasm_bindgen_static_{}",
+                                             s.ident.as_ref().to_lowercase()));
+
+        self.imports.push(Import {
+            attrs: Vec::new(),
+            vis: syn::Visibility::Inherited,
+            function: Function::from_decl(s.ident, &decl),
+            decl,
+            ident,
+            catch: false,
+            class: None,
+            method: false,
+            constructor: false,
+            global: Some(Global {
+                name: s.ident,
+                vis: s.vis.clone(),
+                ty: s.ty.clone(),
+            }),
+            module: module.cloned(),
         });
     }
 
@@ -103,7 +268,36 @@ impl Program {
         shared::Program {
             structs: self.structs.iter().map(|s| s.shared()).collect(),
             free_functions: self.free_functions.iter().map(|s| s.shared()).collect(),
-            imports: self.imports.iter().map(|i| i.function.shared()).collect(),
+            imports: self.imports.iter().map(|i| i.shared()).collect(),
+            enums: self.enums.iter().map(|e| e.shared()).collect(),
+            consts: self.consts.iter().map(|c| c.shared()).collect(),
+        }
+    }
+
+    /// Enums and imported types can't be told apart from structs while
+    /// parsing types, so once all items are known this rewrites uses of them
+    /// to `Type::Enum` and `Type::ImportedType` respectively.
+    pub fn resolve_types(&mut self) {
+        let enums = self.enums.iter().map(|e| e.name).collect::<Vec<_>>();
+        let imported = self.imported_types.iter().map(|t| t.name).collect::<Vec<_>>();
+        for import in self.imports.iter() {
+            if let Some(class) = import.class {
+                if !imported.contains(&class) {
+                    panic!("`{}` is not a type imported in an `extern \"JS\"` block",
+                           class);
+                }
+            }
+        }
+        let functions = self.free_functions.iter_mut()
+            .chain(self.structs.iter_mut().flat_map(|s| {
+                s.functions.iter_mut()
+                    .chain(s.methods.iter_mut().map(|m| &mut m.function))
+            }))
+            .chain(self.imports.iter_mut().map(|i| &mut i.function));
+        for f in functions {
+            for ty in f.arguments.iter_mut().chain(f.ret.as_mut()) {
+                ty.resolve(&enums, &imported);
+            }
         }
     }
 }
@@ -145,12 +339,12 @@ impl Function {
                     _ => panic!("arguments cannot be `self` or ignored"),
                 }
             })
-            .map(|arg| Type::from(&arg.ty))
+            .map(|arg| Type::from_argument(&arg.ty))
             .collect::<Vec<_>>();
 
         let ret = match decl.output {
             syn::ReturnType::Default => None,
-            syn::ReturnType::Type(_, ref t) => Some(Type::from(t)),
+            syn::ReturnType::Type(_, ref t) => Some(Type::from_return(t)),
         };
 
         Function { name, arguments, ret }
@@ -200,6 +394,43 @@ pub fn extract_path_ident(path: &syn::Path) -> syn::Ident {
     path.segments.first().unwrap().value().ident
 }
 
+/// Extracts the name and type parameters of a path like `Vec<u8>`, returning
+/// `None` if the path doesn't have any angle-bracketed type parameters.
+pub fn extract_path_generics(path: &syn::Path)
+    -> Option<(syn::Ident, Vec<&syn::Type>)>
+{
+    if path.leading_colon.is_some() || path.segments.len() != 1 {
+        return None
+    }
+    let segment = path.segments.first().unwrap().value();
+    let args = match segment.arguments {
+        syn::PathArguments::AngleBracketed(ref a) => a,
+        _ => return None,
+    };
+    let types = args.args.iter()
+        .map(|arg| {
+            match *arg {
+                syn::GenericArgument::Type(ref t) => t,
+                _ => panic!("unsupported generic argument in path"),
+            }
+        })
+        .collect();
+    Some((segment.ident, types))
+}
+
+fn vector_element(ty: &syn::Type) -> syn::Ident {
+    let ident = match *ty {
+        syn::Type::Path(syn::TypePath { qself: None, ref path }) => {
+            extract_path_ident(path)
+        }
+        _ => panic!("unsupported slice or vector element type"),
+    };
+    if shared::VectorKind::from_rust(ident.as_ref()).is_none() {
+        panic!("unsupported slice or vector element type `{}`", ident);
+    }
+    ident
+}
+
 impl Type {
     pub fn from(ty: &syn::Type) -> Type {
         match *ty {
@@ -208,8 +439,35 @@ impl Type {
                     panic!("can't have lifetimes on references yet");
                 }
                 let mutable = r.mutability.is_some();
+                if let Some(c) = Closure::from(&r.elem, false) {
+                    if c.mutable != mutable {
+                        panic!("`Fn` closures must be passed as `&Fn(..)` and \
+                                `FnMut` closures as `&mut FnMut(..)`");
+                    }
+                    return Type::Closure(Box::new(c))
+                }
                 match *r.elem {
+                    syn::Type::Slice(ref s) => {
+                        let elem = vector_element(&s.elem);
+                        if mutable {
+                            Type::BorrowedMutSlice(elem)
+                        } else {
+                            Type::BorrowedSlice(elem)
+                        }
+                    }
                     syn::Type::Path(syn::TypePath { qself: None, ref path }) => {
+                        if let Some((ident, args)) = extract_path_generics(path) {
+                            if ident.as_ref() != "Closure" || args.len() != 1 {
+                                panic!("unsupported reference to generic type");
+                            }
+                            if mutable {
+                                panic!("`Closure` must be passed by shared reference");
+                            }
+                            return match Closure::from(args[0], true) {
+                                Some(c) => Type::Closure(Box::new(c)),
+                                None => panic!("`Closure` must contain `Fn(..)` or `FnMut(..)`"),
+                            }
+                        }
                         let ident = extract_path_ident(path);
                         match ident.as_ref() {
                             "str" => {
@@ -244,6 +502,9 @@ impl Type {
                 }
             }
             syn::Type::Path(syn::TypePath { qself: None, ref path }) => {
+                if let Some((ident, args)) = extract_path_generics(path) {
+                    return Type::from_generic(ident, &args)
+                }
                 let ident = extract_path_ident(path);
                 match ident.as_ref() {
                     "i8" |
@@ -258,7 +519,9 @@ impl Type {
                     "f64" => {
                         Type::Integer(ident)
                     }
+                    "i64" | "u64" => Type::Integer64(ident),
                     "bool" => Type::Boolean,
+                    "char" => Type::Char,
                     "String" => Type::String,
                     "JsObject" => Type::JsObject,
                     _ => Type::ByValue(ident),
@@ -268,29 +531,526 @@ impl Type {
         }
     }
 
+    /// Parses the type of a function argument, rejecting types which are
+    /// only allowed in return position.
+    fn from_argument(ty: &syn::Type) -> Type {
+        match Type::from(ty) {
+            Type::Result(_) => {
+                panic!("`Result` can only be used as a return type, not as \
+                        the type of an argument")
+            }
+            t => t,
+        }
+    }
+
+    /// Parses the return type of a function, rejecting types which are only
+    /// allowed as arguments.
+    fn from_return(ty: &syn::Type) -> Type {
+        match Type::from(ty) {
+            Type::Closure(_) => {
+                panic!("closures can only be passed as arguments, they can't \
+                        be returned")
+            }
+            t => t,
+        }
+    }
+
+    fn from_generic(ident: syn::Ident, args: &[&syn::Type]) -> Type {
+        match ident.as_ref() {
+            "Vec" => {
+                if args.len() != 1 {
+                    panic!("`Vec` must have exactly one type parameter");
+                }
+                Type::Vector(vector_element(args[0]))
+            }
+            "Option" => {
+                if args.len() != 1 {
+                    panic!("`Option` must have exactly one type parameter");
+                }
+                let ty = Type::from(args[0]);
+                match ty {
+                    Type::Integer(_) |
+                    Type::Boolean |
+                    Type::BorrowedStr |
+                    Type::String |
+                    Type::JsObject |
+                    Type::ByValue(_) => {}
+                    _ => panic!("unsupported type inside of `Option`"),
+                }
+                Type::Option(Box::new(ty))
+            }
+            // The error type isn't inspected here, the generated code
+            // requires it to implement `Into<JsObject>` instead.
+            "Result" => {
+                if args.len() != 2 {
+                    panic!("`Result` must have exactly two type parameters");
+                }
+                let ty = match *args[0] {
+                    syn::Type::Tuple(ref t) if t.elems.len() == 0 => return Type::Result(None),
+                    ref t => Type::from(t),
+                };
+                if let Type::Result(_) = ty {
+                    panic!("`Result` can't be nested in another `Result`");
+                }
+                Type::Result(Some(Box::new(ty)))
+            }
+            _ => panic!("unsupported generic type `{}`", ident),
+        }
+    }
+
     fn shared(&self) -> shared::Type {
         match *self {
             Type::Integer(_) |
             Type::RawConstPtr(_) |
             Type::RawMutPtr(_) => shared::Type::Number,
+            Type::Integer64(n) => {
+                if n.as_ref() == "i64" {
+                    shared::Type::I64
+                } else {
+                    shared::Type::U64
+                }
+            }
+            Type::Char => shared::Type::Char,
             Type::BorrowedStr => shared::Type::BorrowedStr,
             Type::String => shared::Type::String,
+            Type::BorrowedSlice(e) => shared::Type::BorrowedSlice(vector_kind(e)),
+            Type::BorrowedMutSlice(e) => shared::Type::BorrowedMutSlice(vector_kind(e)),
+            Type::Vector(e) => shared::Type::Vector(vector_kind(e)),
             Type::ByValue(n) => shared::Type::ByValue(n.to_string()),
             Type::ByRef(n) => shared::Type::ByRef(n.to_string()),
             Type::ByMutRef(n) => shared::Type::ByMutRef(n.to_string()),
             Type::JsObject => shared::Type::JsObject,
             Type::JsObjectRef => shared::Type::JsObjectRef,
             Type::Boolean => shared::Type::Boolean,
+            Type::Option(ref t) => shared::Type::Option(Box::new(t.shared())),
+            Type::Result(ref t) => {
+                shared::Type::Result(t.as_ref().map(|t| Box::new(t.shared())))
+            }
+            Type::Enum(n) => shared::Type::Enum(n.to_string()),
+            // Imported types are just `JsObject` values in a Rust newtype
+            Type::ImportedType(_) => shared::Type::JsObject,
+            Type::ImportedTypeRef(_) => shared::Type::JsObjectRef,
+            Type::Closure(ref c) => shared::Type::Closure(Box::new(c.shared())),
+        }
+    }
+
+    fn resolve(&mut self, enums: &[syn::Ident], imported: &[syn::Ident]) {
+        let new = match *self {
+            Type::ByValue(n) if enums.contains(&n) => Type::Enum(n),
+            Type::ByValue(n) if imported.contains(&n) => Type::ImportedType(n),
+            Type::ByRef(n) if imported.contains(&n) => Type::ImportedTypeRef(n),
+            Type::ByMutRef(n) if imported.contains(&n) => {
+                panic!("can't have mutable references to imported type `{}`", n)
+            }
+            Type::Option(ref mut t) => return t.resolve(enums, imported),
+            Type::Result(Some(ref mut t)) => return t.resolve(enums, imported),
+            _ => return,
+        };
+        *self = new;
+    }
+}
+
+impl Closure {
+    /// Parses `Fn(..)` or `FnMut(..)`, returning `None` if `ty` isn't a
+    /// closure type at all.
+    fn from(ty: &syn::Type, owned: bool) -> Option<Closure> {
+        let path = match *ty {
+            syn::Type::Path(syn::TypePath { qself: None, ref path }) => path,
+            syn::Type::TraitObject(ref t) if t.bounds.len() == 1 => {
+                match *t.bounds.first().unwrap().value() {
+                    syn::TypeParamBound::Trait(ref b) => &b.path,
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+        if path.leading_colon.is_some() || path.segments.len() != 1 {
+            return None
+        }
+        let segment = path.segments.first().unwrap().value();
+        let args = match segment.arguments {
+            syn::PathArguments::Parenthesized(ref a) => a,
+            _ => return None,
+        };
+        let mutable = match segment.ident.as_ref() {
+            "Fn" => false,
+            "FnMut" => true,
+            _ => panic!("only `Fn` and `FnMut` closures are supported"),
+        };
+        let arguments = args.inputs.iter()
+            .map(|t| {
+                let t = Type::from(t);
+                match t {
+                    Type::Integer(_) | Type::Boolean | Type::JsObject => t,
+                    _ => panic!("closure arguments can only be numbers, \
+                                 `bool`, or `JsObject`"),
+                }
+            })
+            .collect();
+        let ret = match args.output {
+            syn::ReturnType::Default => None,
+            syn::ReturnType::Type(_, ref t) => {
+                match Type::from(t) {
+                    t @ Type::Integer(_) |
+                    t @ Type::Boolean |
+                    t @ Type::JsObject => Some(t),
+                    _ => panic!("closures can only return numbers, `bool`, \
+                                 or `JsObject`"),
+                }
+            }
+        };
+        Some(Closure { mutable, owned, arguments, ret, ty: ty.clone() })
+    }
+
+    /// Name of the exported function through which JS invokes closures of
+    /// this signature, for example `__wbindgen_closure_fnmut_u32_to_bool`.
+    pub fn trampoline(&self) -> syn::Ident {
+        let mut name = String::from("__wbindgen_closure_");
+        name.push_str(if self.mutable { "fnmut" } else { "fn" });
+        let code = |t: &Type| {
+            match *t {
+                Type::Integer(i) => i.as_ref().to_string(),
+                Type::Boolean => "bool".to_string(),
+                Type::JsObject => "object".to_string(),
+                _ => unreachable!(),
+            }
+        };
+        for arg in self.arguments.iter() {
+            name.push_str("_");
+            name.push_str(&code(arg));
+        }
+        if let Some(ref ret) = self.ret {
+            name.push_str("_to_");
+            name.push_str(&code(ret));
+        }
+        syn::Ident::from(name)
+    }
+
+    fn shared(&self) -> shared::Closure {
+        shared::Closure {
+            mutable: self.mutable,
+            owned: self.owned,
+            function: shared::Function {
+                name: self.trampoline().as_ref().to_string(),
+                arguments: self.arguments.iter().map(|t| t.shared()).collect(),
+                ret: self.ret.as_ref().map(|t| t.shared()),
+            },
         }
     }
 }
 
+impl Enum {
+    pub fn from(e: &syn::ItemEnum) -> Enum {
+        match e.vis {
+            syn::Visibility::Public(_) => {}
+            _ => panic!("only public enums are allowed"),
+        }
+        if e.generics.params.len() > 0 {
+            panic!("generic enums aren't supported");
+        }
+        let mut next = 0;
+        let variants = e.variants.iter()
+            .map(|v| {
+                match v.fields {
+                    syn::Fields::Unit => {}
+                    _ => panic!("only C-like enums are supported"),
+                }
+                let value = match v.discriminant {
+                    Some((_, ref expr)) => {
+                        let (negative, lit) = match *expr {
+                            syn::Expr::Unary(syn::ExprUnary {
+                                op: syn::UnOp::Neg(_),
+                                ref expr,
+                                ..
+                            }) => (true, &**expr),
+                            ref expr => (false, expr),
+                        };
+                        let n = match *lit {
+                            syn::Expr::Lit(syn::ExprLit {
+                                lit: syn::Lit::Int(ref i),
+                                ..
+                            }) => i.value(),
+                            _ => panic!("enum discriminants must be integer literals"),
+                        };
+                        if n > 1 << 31 || (!negative && n == 1 << 31) {
+                            panic!("enum discriminants must fit in an `i32`");
+                        }
+                        if negative { (n as i64).wrapping_neg() as i32 } else { n as i32 }
+                    }
+                    None => next,
+                };
+                next = value.wrapping_add(1);
+                Variant { name: v.ident, value }
+            })
+            .collect();
+        Enum { name: e.ident, variants }
+    }
+
+    fn shared(&self) -> shared::Enum {
+        shared::Enum {
+            name: self.name.as_ref().to_string(),
+            variants: self.variants.iter()
+                .map(|v| {
+                    shared::EnumVariant {
+                        name: v.name.as_ref().to_string(),
+                        value: v.value,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Const {
+    pub fn from(c: &syn::ItemConst) -> Const {
+        match c.vis {
+            syn::Visibility::Public(_) => {}
+            _ => panic!("only public consts are allowed"),
+        }
+        let value = match const_kind(&c.ty) {
+            ConstKind::Number => {
+                let (negative, lit) = match *c.expr {
+                    syn::Expr::Unary(syn::ExprUnary {
+                        op: syn::UnOp::Neg(_),
+                        ref expr,
+                        ..
+                    }) => (true, &**expr),
+                    ref expr => (false, expr),
+                };
+                let n = match *lit {
+                    syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(ref i), .. }) => {
+                        // Beyond 2^53 not every integer is representable as
+                        // a JS number, so the exported value could differ
+                        if i.value() > 1 << 53 {
+                            panic!("integer const `{}` is too large to be \
+                                    represented exactly in JS, the limit is \
+                                    2^53", c.ident);
+                        }
+                        i.value() as f64
+                    }
+                    syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Float(ref f), .. }) => {
+                        f.value()
+                    }
+                    _ => panic!("numeric consts must be integer or float literals"),
+                };
+                shared::ConstValue::Number(if negative { -n } else { n })
+            }
+            ConstKind::Boolean => {
+                match *c.expr {
+                    syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Bool(ref b), .. }) => {
+                        shared::ConstValue::Boolean(b.value)
+                    }
+                    _ => panic!("bool consts must be `true` or `false`"),
+                }
+            }
+            ConstKind::String => {
+                match *c.expr {
+                    syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(ref s), .. }) => {
+                        shared::ConstValue::String(s.value())
+                    }
+                    _ => panic!("string consts must be string literals"),
+                }
+            }
+        };
+        Const { name: c.ident, value }
+    }
+
+    fn shared(&self) -> shared::Const {
+        shared::Const {
+            name: self.name.as_ref().to_string(),
+            value: self.value.clone(),
+        }
+    }
+}
+
+enum ConstKind {
+    Number,
+    Boolean,
+    String,
+}
+
+fn const_kind(ty: &syn::Type) -> ConstKind {
+    match *ty {
+        syn::Type::Reference(ref r) => {
+            // Consts can only borrow for `'static` anyway, so there's no need
+            // to look at the lifetime here.
+            if r.mutability.is_some() {
+                panic!("string consts must be `&'static str`");
+            }
+            match *r.elem {
+                syn::Type::Path(syn::TypePath { qself: None, ref path })
+                    if extract_path_ident(path).as_ref() == "str" => ConstKind::String,
+                _ => panic!("string consts must be `&'static str`"),
+            }
+        }
+        syn::Type::Path(syn::TypePath { qself: None, ref path }) => {
+            match extract_path_ident(path).as_ref() {
+                "i8" | "u8" | "i16" | "u16" | "i32" | "u32" | "i64" | "u64" |
+                "isize" | "usize" | "f32" | "f64" => ConstKind::Number,
+                "bool" => ConstKind::Boolean,
+                _ => panic!("consts must be numbers, `bool`, or `&'static str`"),
+            }
+        }
+        _ => panic!("consts must be numbers, `bool`, or `&'static str`"),
+    }
+}
+
+impl Import {
+    pub fn shim_name(&self) -> syn::Ident {
+        syn::Ident::from(self.shared().shim_name())
+    }
+
+    fn shared(&self) -> shared::Import {
+        shared::Import {
+            function: self.function.shared(),
+            catch: self.catch,
+            class: self.class.map(|c| c.as_ref().to_string()),
+            method: self.method,
+            constructor: self.constructor,
+            global: self.global.is_some(),
+            module: self.module.clone(),
+        }
+    }
+}
+
+/// Options specified through `#[wasm_bindgen(...)]` attributes on an item.
+pub struct BindgenAttrs {
+    attrs: Vec<syn::NestedMeta>,
+}
+
+impl BindgenAttrs {
+    pub fn find(attrs: &[syn::Attribute]) -> BindgenAttrs {
+        let attrs = attrs.iter()
+            .filter_map(|a| a.interpret_meta())
+            .filter_map(|m| {
+                match m {
+                    syn::Meta::List(list) => {
+                        if list.ident.as_ref() == "wasm_bindgen" {
+                            Some(list.nested)
+                        } else {
+                            None
+                        }
+                    }
+                    _ => None,
+                }
+            })
+            .flat_map(|nested| nested.into_iter())
+            .collect();
+        BindgenAttrs { attrs }
+    }
+
+    /// Removes all `#[wasm_bindgen(...)]` attributes from `attrs`, as rustc
+    /// wouldn't understand them when the item is emitted again.
+    pub fn strip(attrs: &mut Vec<syn::Attribute>) {
+        attrs.retain(|a| {
+            match a.interpret_meta() {
+                Some(syn::Meta::List(ref list)) => list.ident.as_ref() != "wasm_bindgen",
+                _ => true,
+            }
+        });
+    }
+
+    fn has_word(&self, word: &str) -> bool {
+        self.attrs.iter().any(|a| {
+            match *a {
+                syn::NestedMeta::Meta(syn::Meta::Word(ref w)) => w.as_ref() == word,
+                _ => false,
+            }
+        })
+    }
+
+    pub fn catch(&self) -> bool {
+        self.has_word("catch")
+    }
+
+    pub fn constructor(&self) -> bool {
+        self.has_word("constructor")
+    }
+
+    pub fn method(&self) -> bool {
+        self.has_word("method")
+    }
+
+    pub fn static_method_of(&self) -> Option<String> {
+        self.name_value("static_method_of")
+    }
+
+    pub fn module(&self) -> Option<String> {
+        self.name_value("module")
+    }
+
+    fn name_value(&self, name: &str) -> Option<String> {
+        self.attrs.iter()
+            .filter_map(|a| {
+                match *a {
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(ref nv)) => Some(nv),
+                    _ => None,
+                }
+            })
+            .filter(|nv| nv.ident.as_ref() == name)
+            .map(|nv| {
+                match nv.lit {
+                    syn::Lit::Str(ref s) => s.value(),
+                    _ => panic!("`{}` must be a string literal", name),
+                }
+            })
+            .next()
+    }
+}
+
+/// Returns the type of a `pub` struct field if it's one that can be exposed
+/// to JS as a property, namely a number, `bool`, `String`, or `JsObject`.
+fn field_type(ty: &syn::Type) -> Option<Type> {
+    let path = match *ty {
+        syn::Type::Path(syn::TypePath { qself: None, ref path }) => path,
+        _ => return None,
+    };
+    if path.leading_colon.is_some() || path.segments.len() != 1 {
+        return None
+    }
+    match path.segments.first().unwrap().value().arguments {
+        syn::PathArguments::None => {}
+        _ => return None,
+    }
+    match Type::from(ty) {
+        t @ Type::Integer(_) |
+        t @ Type::Boolean |
+        t @ Type::String |
+        t @ Type::JsObject => Some(t),
+        _ => None,
+    }
+}
+
+pub fn vector_kind(elem: syn::Ident) -> shared::VectorKind {
+    shared::VectorKind::from_rust(elem.as_ref()).unwrap()
+}
+
 impl Struct {
     pub fn from(s: &syn::ItemStruct) -> Struct {
+        let mut fields = Vec::new();
+        if let syn::Fields::Named(ref names) = s.fields {
+            for field in names.named.iter() {
+                match field.vis {
+                    syn::Visibility::Public(_) => {}
+                    _ => continue,
+                }
+                // Fields of other types stay visible to Rust only
+                let ty = match field_type(&field.ty) {
+                    Some(ty) => ty,
+                    None => continue,
+                };
+                fields.push(StructField {
+                    name: field.ident.unwrap(),
+                    ty,
+                });
+            }
+        }
         Struct {
             name: s.ident,
             methods: Vec::new(),
             functions: Vec::new(),
+            fields,
+            constructor: None,
         }
     }
 
@@ -298,6 +1058,14 @@ impl Struct {
         syn::Ident::from(self.shared().free_function())
     }
 
+    pub fn field_getter(&self, field: &StructField) -> syn::Ident {
+        syn::Ident::from(self.shared().field_getter(field.name.as_ref()))
+    }
+
+    pub fn field_setter(&self, field: &StructField) -> syn::Ident {
+        syn::Ident::from(self.shared().field_setter(field.name.as_ref()))
+    }
+
     pub fn push_item(&mut self, item: &syn::ImplItem) {
         let method = match *item {
             syn::ImplItem::Const(_) => panic!("const definitions aren't supported"),
@@ -328,12 +1096,16 @@ impl Struct {
         }
 
         let mut mutable = None;
+        let mut consume = false;
         let arguments = method.sig.decl.inputs.iter()
             .filter_map(|arg| {
                 match *arg {
                     syn::FnArg::Captured(ref c) => Some(c),
                     syn::FnArg::SelfValue(_) => {
-                        panic!("by-value `self` not yet supported");
+                        assert!(mutable.is_none());
+                        consume = true;
+                        mutable = Some(true);
+                        None
                     }
                     syn::FnArg::SelfRef(ref a) => {
                         assert!(mutable.is_none());
@@ -343,18 +1115,42 @@ impl Struct {
                     _ => panic!("arguments cannot be `self` or ignored"),
                 }
             })
-            .map(|arg| Type::from(&arg.ty))
+            .map(|arg| Type::from_argument(&arg.ty))
             .collect::<Vec<_>>();
 
         let ret = match method.sig.decl.output {
             syn::ReturnType::Default => None,
-            syn::ReturnType::Type(_, ref t) => Some(Type::from(t)),
+            syn::ReturnType::Type(_, ref t) => Some(Type::from_return(t)),
         };
 
         let function = Function { name: method.sig.ident, arguments, ret };
+
+        if BindgenAttrs::find(&method.attrs).constructor() {
+            if mutable.is_some() {
+                panic!("constructors can't take `self`");
+            }
+            if self.constructor.is_some() {
+                panic!("only one constructor is allowed per struct");
+            }
+            let returns_self = match function.ret {
+                Some(Type::ByValue(n)) => n == self.name,
+                Some(Type::Result(Some(ref t))) => {
+                    match **t {
+                        Type::ByValue(n) => n == self.name,
+                        _ => false,
+                    }
+                }
+                _ => false,
+            };
+            if !returns_self {
+                panic!("constructors must return `{}`", self.name);
+            }
+            self.constructor = Some(function.name);
+        }
+
         match mutable {
             Some(mutable) => {
-                self.methods.push(Method { mutable, function });
+                self.methods.push(Method { mutable, consume, function });
             }
             None => {
                 self.functions.push(function);
@@ -367,6 +1163,17 @@ impl Struct {
             name: self.name.to_string(),
             functions: self.functions.iter().map(|f| f.shared()).collect(),
             methods: self.methods.iter().map(|f| f.shared()).collect(),
+            fields: self.fields.iter().map(|f| f.shared()).collect(),
+            constructor: self.constructor.map(|c| c.as_ref().to_string()),
+        }
+    }
+}
+
+impl StructField {
+    pub fn shared(&self) -> shared::StructField {
+        shared::StructField {
+            name: self.name.as_ref().to_string(),
+            ty: self.ty.shared(),
         }
     }
 }
@@ -375,6 +1182,7 @@ impl Method {
     pub fn shared(&self) -> shared::Method {
         shared::Method {
             mutable: self.mutable,
+            consume: self.consume,
             function: self.function.shared(),
         }
     }
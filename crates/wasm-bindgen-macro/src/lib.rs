@@ -8,6 +8,8 @@ extern crate proc_macro2;
 extern crate serde_json;
 extern crate wasm_bindgen_shared;
 
+use std::cell::RefCell;
+use std::collections::HashSet;
 use std::sync::atomic::*;
 
 use proc_macro::TokenStream;
@@ -18,6 +20,19 @@ mod ast;
 
 static MALLOC_GENERATED: AtomicBool = ATOMIC_BOOL_INIT;
 static BOXED_STR_GENERATED: AtomicBool = ATOMIC_BOOL_INIT;
+static GLOBAL_ARGUMENT_GENERATED: AtomicBool = ATOMIC_BOOL_INIT;
+// A bitmask, indexed by `VectorKind`, of which boxed vector shims have been
+// generated.
+static BOXED_VEC_GENERATED: AtomicUsize = ATOMIC_USIZE_INIT;
+// A bitmask, indexed by `VectorKind`, of which typed malloc/free pairs have
+// been generated.
+static VEC_MALLOC_GENERATED: AtomicUsize = ATOMIC_USIZE_INIT;
+
+thread_local! {
+    // Names of the closure trampolines which have been generated, as there's
+    // one per closure signature rather than a fixed set.
+    static CLOSURES_GENERATED: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
 
 macro_rules! my_quote {
     ($($t:tt)*) => (quote_spanned!(Span::call_site() => $($t)*))
@@ -35,6 +50,9 @@ pub fn wasm_bindgen(input: TokenStream) -> TokenStream {
         structs: Vec::new(),
         free_functions: Vec::new(),
         imports: Vec::new(),
+        enums: Vec::new(),
+        consts: Vec::new(),
+        imported_types: Vec::new(),
     };
 
     // Translate all input items into our own internal representation (the `ast`
@@ -55,15 +73,30 @@ pub fn wasm_bindgen(input: TokenStream) -> TokenStream {
                 program.structs.push(s);
             }
             syn::Item::Impl(ref i) => {
-                item.to_tokens(&mut ret);
                 program.push_impl(i);
+                let mut i = i.clone();
+                for item in i.items.iter_mut() {
+                    if let syn::ImplItem::Method(ref mut m) = *item {
+                        ast::BindgenAttrs::strip(&mut m.attrs);
+                    }
+                }
+                i.to_tokens(&mut ret);
             }
             syn::Item::ForeignMod(ref f) => {
                 program.push_foreign_mod(f);
             }
+            syn::Item::Enum(ref e) => {
+                item.to_tokens(&mut ret);
+                program.enums.push(ast::Enum::from(e));
+            }
+            syn::Item::Const(ref c) => {
+                item.to_tokens(&mut ret);
+                program.consts.push(ast::Const::from(c));
+            }
             _ => panic!("unexpected item in bindgen macro"),
         }
     }
+    program.resolve_types();
 
     // Generate wrappers for all the items that we've found
 
@@ -73,9 +106,15 @@ pub fn wasm_bindgen(input: TokenStream) -> TokenStream {
     for s in program.structs.iter() {
         bindgen_struct(s, &mut ret);
     }
+    for t in program.imported_types.iter() {
+        bindgen_imported_type(t, &mut ret);
+    }
     for i in program.imports.iter() {
         bindgen_import(i, &mut ret);
     }
+    for e in program.enums.iter() {
+        bindgen_enum(e, &mut ret);
+    }
 
     // Finally generate a static which will eventually be what lives in a custom
     // section of the wasm executable. For now it's just a plain old static, but
@@ -121,6 +160,9 @@ fn bindgen_struct(s: &ast::Struct, into: &mut Tokens) {
     for f in s.methods.iter() {
         bindgen_struct_method(s, f, into);
     }
+    for f in s.fields.iter() {
+        bindgen_struct_field(s, f, into);
+    }
 
     let name = &s.name;
     let free_fn = s.free_function();
@@ -133,6 +175,29 @@ fn bindgen_struct(s: &ast::Struct, into: &mut Tokens) {
     }).to_tokens(into);
 }
 
+fn bindgen_enum(e: &ast::Enum, into: &mut Tokens) {
+    let name = &e.name;
+    let checks = e.variants.iter()
+        .map(|v| {
+            let variant = v.name;
+            my_quote! {
+                if n == #name::#variant as u32 {
+                    return #name::#variant
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    (my_quote! {
+        impl #name {
+            #[doc(hidden)]
+            pub fn __wbindgen_from_u32(n: u32) -> #name {
+                #(#checks)*
+                ::wasm_bindgen::throw("invalid enum value passed")
+            }
+        }
+    }).to_tokens(into);
+}
+
 fn bindgen_struct_fn(s: &ast::Struct, f: &ast::Function, into: &mut Tokens) {
     bindgen(&f.struct_function_export_name(s.name),
             f.rust_symbol(Some(s.name)),
@@ -145,16 +210,40 @@ fn bindgen_struct_fn(s: &ast::Struct, f: &ast::Function, into: &mut Tokens) {
 fn bindgen_struct_method(s: &ast::Struct, m: &ast::Method, into: &mut Tokens) {
     bindgen(&m.function.struct_function_export_name(s.name),
             m.function.rust_symbol(Some(s.name)),
-            Receiver::StructMethod(s.name, m.mutable, m.function.name),
+            if m.consume {
+                Receiver::StructConsumingMethod(s.name, m.function.name)
+            } else {
+                Receiver::StructMethod(s.name, m.mutable, m.function.name)
+            },
             &m.function.arguments,
             m.function.ret.as_ref(),
             into)
 }
 
+fn bindgen_struct_field(s: &ast::Struct, f: &ast::StructField, into: &mut Tokens) {
+    let getter = s.field_getter(f);
+    bindgen(&syn::LitStr::new(getter.as_ref(), Span::def_site()),
+            getter,
+            Receiver::StructFieldGet(s.name, f.name),
+            &[],
+            Some(&f.ty),
+            into);
+    let setter = s.field_setter(f);
+    bindgen(&syn::LitStr::new(setter.as_ref(), Span::def_site()),
+            setter,
+            Receiver::StructFieldSet(s.name, f.name),
+            &[f.ty.clone()],
+            None,
+            into);
+}
+
 enum Receiver {
     FreeFunction(syn::Ident),
     StructFunction(syn::Ident, syn::Ident),
     StructMethod(syn::Ident, bool, syn::Ident),
+    StructConsumingMethod(syn::Ident, syn::Ident),
+    StructFieldGet(syn::Ident, syn::Ident),
+    StructFieldSet(syn::Ident, syn::Ident),
 }
 
 fn bindgen(export_name: &syn::LitStr,
@@ -169,10 +258,19 @@ fn bindgen(export_name: &syn::LitStr,
     let ret = syn::Ident::from("_ret");
 
     let mut malloc = false;
+    let mut vec_mallocs = Vec::new();
     let mut boxed_str = false;
+    let mut boxed_vec = None;
+    let mut global_argument = false;
 
     let mut offset = 0;
-    if let Receiver::StructMethod(class, _, _) = receiver {
+    let class = match receiver {
+        Receiver::StructMethod(class, _, _) |
+        Receiver::StructFieldGet(class, _) |
+        Receiver::StructFieldSet(class, _) => Some(class),
+        _ => None,
+    };
+    if let Some(class) = class {
         args.push(my_quote! { me: *mut ::wasm_bindgen::__rt::WasmRefCell<#class> });
         arg_conversions.push(my_quote! {
             ::wasm_bindgen::__rt::assert_not_null(me);
@@ -180,6 +278,17 @@ fn bindgen(export_name: &syn::LitStr,
         });
         offset = 1;
     }
+    if let Receiver::StructConsumingMethod(class, _) = receiver {
+        args.push(my_quote! { me: *mut ::wasm_bindgen::__rt::WasmRefCell<#class> });
+        arg_conversions.push(my_quote! {
+            ::wasm_bindgen::__rt::assert_not_null(me);
+            let me = unsafe {
+                (*me).borrow_mut();
+                Box::from_raw(me).into_inner()
+            };
+        });
+        offset = 1;
+    }
 
     for (i, ty) in arguments.iter().enumerate() {
         let i = i + offset;
@@ -188,6 +297,24 @@ fn bindgen(export_name: &syn::LitStr,
             ast::Type::Integer(i) => {
                 args.push(my_quote! { #ident: #i });
             }
+            ast::Type::Char => {
+                args.push(my_quote! { #ident: u32 });
+                arg_conversions.push(my_quote! {
+                    let #ident = match ::std::char::from_u32(#ident) {
+                        Some(c) => c,
+                        None => ::wasm_bindgen::throw("invalid `char` argument"),
+                    };
+                });
+            }
+            ast::Type::Integer64(t) => {
+                let lo = syn::Ident::from(format!("arg{}_lo", i));
+                let hi = syn::Ident::from(format!("arg{}_hi", i));
+                args.push(my_quote! { #lo: u32 });
+                args.push(my_quote! { #hi: u32 });
+                arg_conversions.push(my_quote! {
+                    let #ident = (((#hi as u64) << 32) | #lo as u64) as #t;
+                });
+            }
             ast::Type::Boolean => {
                 args.push(my_quote! { #ident: u32 });
                 arg_conversions.push(my_quote! {
@@ -226,6 +353,42 @@ fn bindgen(export_name: &syn::LitStr,
                     };
                 });
             }
+            ast::Type::BorrowedSlice(elem) => {
+                vec_mallocs.extend(vec_malloc_tokens(elem));
+                let ptr = syn::Ident::from(format!("arg{}_ptr", i));
+                let len = syn::Ident::from(format!("arg{}_len", i));
+                args.push(my_quote! { #ptr: *const #elem });
+                args.push(my_quote! { #len: usize });
+                arg_conversions.push(my_quote! {
+                    let #ident = unsafe {
+                        ::std::slice::from_raw_parts(#ptr, #len)
+                    };
+                });
+            }
+            ast::Type::BorrowedMutSlice(elem) => {
+                vec_mallocs.extend(vec_malloc_tokens(elem));
+                let ptr = syn::Ident::from(format!("arg{}_ptr", i));
+                let len = syn::Ident::from(format!("arg{}_len", i));
+                args.push(my_quote! { #ptr: *mut #elem });
+                args.push(my_quote! { #len: usize });
+                arg_conversions.push(my_quote! {
+                    let #ident = unsafe {
+                        ::std::slice::from_raw_parts_mut(#ptr, #len)
+                    };
+                });
+            }
+            ast::Type::Vector(elem) => {
+                vec_mallocs.extend(vec_malloc_tokens(elem));
+                let ptr = syn::Ident::from(format!("arg{}_ptr", i));
+                let len = syn::Ident::from(format!("arg{}_len", i));
+                args.push(my_quote! { #ptr: *mut #elem });
+                args.push(my_quote! { #len: usize });
+                arg_conversions.push(my_quote! {
+                    let #ident = unsafe {
+                        ::std::vec::Vec::from_raw_parts(#ptr, #len, #len)
+                    };
+                });
+            }
             ast::Type::ByValue(name) => {
                 args.push(my_quote! { #ident: *mut ::wasm_bindgen::__rt::WasmRefCell<#name> });
                 arg_conversions.push(my_quote! {
@@ -267,9 +430,145 @@ fn bindgen(export_name: &syn::LitStr,
                     let #ident = &*#ident;
                 });
             }
+            ast::Type::Option(ref t) => {
+                match **t {
+                    ast::Type::Integer(i) => {
+                        let some = syn::Ident::from(format!("{}_some", ident));
+                        args.push(my_quote! { #some: u32 });
+                        args.push(my_quote! { #ident: #i });
+                        arg_conversions.push(my_quote! {
+                            let #ident = if #some != 0 { Some(#ident) } else { None };
+                        });
+                    }
+                    ast::Type::Boolean => {
+                        args.push(my_quote! { #ident: u32 });
+                        arg_conversions.push(my_quote! {
+                            let #ident = match #ident {
+                                0 => Some(false),
+                                1 => Some(true),
+                                _ => None,
+                            };
+                        });
+                    }
+                    ast::Type::BorrowedStr => {
+                        malloc = malloc || !MALLOC_GENERATED.swap(true, Ordering::SeqCst);
+                        let ptr = syn::Ident::from(format!("arg{}_ptr", i));
+                        let len = syn::Ident::from(format!("arg{}_len", i));
+                        args.push(my_quote! { #ptr: *const u8 });
+                        args.push(my_quote! { #len: usize });
+                        arg_conversions.push(my_quote! {
+                            let #ident = if #ptr.is_null() {
+                                None
+                            } else {
+                                Some(unsafe {
+                                    let slice = ::std::slice::from_raw_parts(#ptr, #len);
+                                    ::std::str::from_utf8_unchecked(slice)
+                                })
+                            };
+                        });
+                    }
+                    ast::Type::String => {
+                        malloc = malloc || !MALLOC_GENERATED.swap(true, Ordering::SeqCst);
+                        let ptr = syn::Ident::from(format!("arg{}_ptr", i));
+                        let len = syn::Ident::from(format!("arg{}_len", i));
+                        args.push(my_quote! { #ptr: *mut u8 });
+                        args.push(my_quote! { #len: usize });
+                        arg_conversions.push(my_quote! {
+                            let #ident = if #ptr.is_null() {
+                                None
+                            } else {
+                                Some(unsafe {
+                                    let vec = ::std::vec::Vec::from_raw_parts(#ptr, #len, #len);
+                                    ::std::string::String::from_utf8_unchecked(vec)
+                                })
+                            };
+                        });
+                    }
+                    ast::Type::JsObject => {
+                        args.push(my_quote! { #ident: u32 });
+                        arg_conversions.push(my_quote! {
+                            let #ident = if #ident == u32::max_value() {
+                                None
+                            } else {
+                                Some(::wasm_bindgen::JsObject::__from_idx(#ident))
+                            };
+                        });
+                    }
+                    ast::Type::ByValue(name) => {
+                        args.push(my_quote! { #ident: *mut ::wasm_bindgen::__rt::WasmRefCell<#name> });
+                        arg_conversions.push(my_quote! {
+                            let #ident = if #ident.is_null() {
+                                None
+                            } else {
+                                Some(unsafe {
+                                    (*#ident).borrow_mut();
+                                    Box::from_raw(#ident).into_inner()
+                                })
+                            };
+                        });
+                    }
+                    _ => panic!("unsupported type inside of `Option`"),
+                }
+            }
+            ast::Type::Result(_) => panic!("`Result` can only be returned"),
+            ast::Type::Enum(name) => {
+                args.push(my_quote! { #ident: u32 });
+                arg_conversions.push(my_quote! {
+                    let #ident = #name::__wbindgen_from_u32(#ident);
+                });
+            }
+            ast::Type::ImportedType(name) => {
+                args.push(my_quote! { #ident: u32 });
+                arg_conversions.push(my_quote! {
+                    let #ident = #name {
+                        obj: ::wasm_bindgen::JsObject::__from_idx(#ident),
+                    };
+                });
+            }
+            ast::Type::ImportedTypeRef(name) => {
+                args.push(my_quote! { #ident: u32 });
+                arg_conversions.push(my_quote! {
+                    let #ident = ::std::mem::ManuallyDrop::new(#name {
+                        obj: ::wasm_bindgen::JsObject::__from_idx(#ident),
+                    });
+                    let #ident = &*#ident;
+                });
+            }
+            ast::Type::Closure(_) => {
+                panic!("closures can only be passed to imported functions");
+            }
         }
         converted_arguments.push(my_quote! { #ident });
     }
+    // An `Err` is stashed in the global argument for the JS glue to throw,
+    // while an `Ok` is converted below like any other return value.
+    let mut catch_err = my_quote! {};
+    let ret_type = match ret_type {
+        Some(&ast::Type::Result(ref t)) => {
+            global_argument = global_argument ||
+                !GLOBAL_ARGUMENT_GENERATED.swap(true, Ordering::SeqCst);
+            catch_err = my_quote! {
+                let #ret = match #ret {
+                    Ok(val) => {
+                        unsafe {
+                            ::wasm_bindgen::__rt::GLOBAL_ARGUMENT[1] = 0;
+                        }
+                        val
+                    }
+                    Err(e) => {
+                        let e: ::wasm_bindgen::JsObject = e.into();
+                        unsafe {
+                            ::wasm_bindgen::__rt::GLOBAL_ARGUMENT[1] = 1;
+                            ::wasm_bindgen::__rt::GLOBAL_ARGUMENT[2] = e.__into_idx();
+                            return ::std::mem::zeroed()
+                        }
+                    }
+                };
+            };
+            t.as_ref().map(|t| &**t)
+        }
+        other => other,
+    };
     let ret_ty;
     let convert_ret;
     match ret_type {
@@ -277,6 +576,24 @@ fn bindgen(export_name: &syn::LitStr,
             ret_ty = my_quote! { -> #i };
             convert_ret = my_quote! { #ret };
         }
+        Some(&ast::Type::Char) => {
+            ret_ty = my_quote! { -> u32 };
+            convert_ret = my_quote! { #ret as u32 };
+        }
+        Some(&ast::Type::Integer64(_)) => {
+            // wasm can't return 64-bit integers to JS, so the low half is
+            // returned directly and the high half goes in the global argument
+            global_argument = global_argument ||
+                !GLOBAL_ARGUMENT_GENERATED.swap(true, Ordering::SeqCst);
+            ret_ty = my_quote! { -> u32 };
+            convert_ret = my_quote! {
+                let val = #ret as u64;
+                unsafe {
+                    ::wasm_bindgen::__rt::GLOBAL_ARGUMENT[0] = (val >> 32) as u32;
+                }
+                val as u32
+            };
+        }
         Some(&ast::Type::Boolean) => {
             ret_ty = my_quote! { -> u32 };
             convert_ret = my_quote! { #ret as u32 };
@@ -290,6 +607,8 @@ fn bindgen(export_name: &syn::LitStr,
             convert_ret = my_quote! { #ret };
         }
         Some(&ast::Type::BorrowedStr) => panic!("can't return a borrowed string"),
+        Some(&ast::Type::BorrowedSlice(_)) |
+        Some(&ast::Type::BorrowedMutSlice(_)) => panic!("can't return a borrowed slice"),
         Some(&ast::Type::ByRef(_)) => panic!("can't return a borrowed ref"),
         Some(&ast::Type::ByMutRef(_)) => panic!("can't return a borrowed ref"),
         Some(&ast::Type::String) => {
@@ -297,6 +616,15 @@ fn bindgen(export_name: &syn::LitStr,
             ret_ty = my_quote! { -> *mut String };
             convert_ret = my_quote! { Box::into_raw(Box::new(#ret)) };
         }
+        Some(&ast::Type::Vector(elem)) => {
+            let kind = ast::vector_kind(elem);
+            let bit = 1 << (kind as usize);
+            if BOXED_VEC_GENERATED.fetch_or(bit, Ordering::SeqCst) & bit == 0 {
+                boxed_vec = Some(elem);
+            }
+            ret_ty = my_quote! { -> *mut Vec<#elem> };
+            convert_ret = my_quote! { Box::into_raw(Box::new(#ret)) };
+        }
         Some(&ast::Type::ByValue(name)) => {
             ret_ty = my_quote! { -> *mut ::wasm_bindgen::__rt::WasmRefCell<#name> };
             convert_ret = my_quote! {
@@ -312,6 +640,85 @@ fn bindgen(export_name: &syn::LitStr,
         Some(&ast::Type::JsObjectRef) => {
             panic!("can't return a borrowed ref");
         }
+        Some(&ast::Type::Option(ref t)) => {
+            match **t {
+                ast::Type::Integer(i) => {
+                    // The value itself is returned directly and whether it's
+                    // present is communicated through the global argument
+                    global_argument = global_argument ||
+                        !GLOBAL_ARGUMENT_GENERATED.swap(true, Ordering::SeqCst);
+                    ret_ty = my_quote! { -> #i };
+                    convert_ret = my_quote! {
+                        let (some, val) = match #ret {
+                            Some(val) => (1, val),
+                            None => (0, 0 as #i),
+                        };
+                        unsafe {
+                            ::wasm_bindgen::__rt::GLOBAL_ARGUMENT[0] = some;
+                        }
+                        val
+                    };
+                }
+                ast::Type::Boolean => {
+                    ret_ty = my_quote! { -> u32 };
+                    convert_ret = my_quote! {
+                        match #ret {
+                            Some(b) => b as u32,
+                            None => 2,
+                        }
+                    };
+                }
+                ast::Type::String => {
+                    boxed_str = !BOXED_STR_GENERATED.swap(true, Ordering::SeqCst);
+                    ret_ty = my_quote! { -> *mut String };
+                    convert_ret = my_quote! {
+                        match #ret {
+                            Some(s) => Box::into_raw(Box::new(s)),
+                            None => ::std::ptr::null_mut(),
+                        }
+                    };
+                }
+                ast::Type::JsObject => {
+                    ret_ty = my_quote! { -> u32 };
+                    convert_ret = my_quote! {
+                        match #ret {
+                            Some(obj) => ::wasm_bindgen::JsObject::__into_idx(obj),
+                            None => u32::max_value(),
+                        }
+                    };
+                }
+                ast::Type::ByValue(name) => {
+                    ret_ty = my_quote! { -> *mut ::wasm_bindgen::__rt::WasmRefCell<#name> };
+                    convert_ret = my_quote! {
+                        match #ret {
+                            Some(val) => {
+                                Box::into_raw(Box::new(
+                                    ::wasm_bindgen::__rt::WasmRefCell::new(val)
+                                ))
+                            }
+                            None => ::std::ptr::null_mut(),
+                        }
+                    };
+                }
+                ast::Type::BorrowedStr => panic!("can't return a borrowed string"),
+                _ => panic!("unsupported type inside of `Option`"),
+            }
+        }
+        Some(&ast::Type::Result(_)) => unreachable!(),
+        Some(&ast::Type::Enum(_)) => {
+            ret_ty = my_quote! { -> u32 };
+            convert_ret = my_quote! { #ret as u32 };
+        }
+        Some(&ast::Type::ImportedType(_)) => {
+            ret_ty = my_quote! { -> u32 };
+            convert_ret = my_quote! {
+                ::wasm_bindgen::JsObject::__into_idx(#ret.obj)
+            };
+        }
+        Some(&ast::Type::ImportedTypeRef(_)) => {
+            panic!("can't return a borrowed ref");
+        }
+        Some(&ast::Type::Closure(_)) => panic!("can't return a closure"),
         None => {
             ret_ty = my_quote! {};
             convert_ret = my_quote! {};
@@ -319,29 +726,27 @@ fn bindgen(export_name: &syn::LitStr,
     }
 
     let malloc = if malloc {
+        malloc_tokens()
+    } else {
+        my_quote! {
+        }
+    };
+
+    let boxed_str = if boxed_str {
         my_quote! {
             #[no_mangle]
-            pub extern fn __wbindgen_malloc(size: usize) -> *mut u8 {
-                // Any malloc request this big is bogus anyway. If this actually
-                // goes down to `Vec` we trigger a whole bunch of panicking
-                // machinery to get pulled in from libstd anyway as it'll verify
-                // the size passed in below.
-                //
-                // Head this all off by just aborting on too-big sizes. This
-                // avoids panicking (code bloat) and gives a better error
-                // message too hopefully.
-                if size >= usize::max_value() / 2 {
-                    ::wasm_bindgen::throw("invalid malloc request");
-                }
-                let mut ret = Vec::with_capacity(size);
-                let ptr = ret.as_mut_ptr();
-                ::std::mem::forget(ret);
-                return ptr
+            pub unsafe extern fn __wbindgen_boxed_str_len(ptr: *mut String) -> usize {
+                (*ptr).len()
             }
 
             #[no_mangle]
-            pub unsafe extern fn __wbindgen_free(ptr: *mut u8, size: usize) {
-                drop(Vec::<u8>::from_raw_parts(ptr, 0, size));
+            pub unsafe extern fn __wbindgen_boxed_str_ptr(ptr: *mut String) -> *const u8 {
+                (*ptr).as_ptr()
+            }
+
+            #[no_mangle]
+            pub unsafe extern fn __wbindgen_boxed_str_free(ptr: *mut String) {
+                drop(Box::from_raw(ptr));
             }
         }
     } else {
@@ -349,20 +754,24 @@ fn bindgen(export_name: &syn::LitStr,
         }
     };
 
-    let boxed_str = if boxed_str {
+    let boxed_vec = if let Some(elem) = boxed_vec {
+        let kind = ast::vector_kind(elem);
+        let len = syn::Ident::from(kind.boxed_vec_function("len"));
+        let ptr = syn::Ident::from(kind.boxed_vec_function("ptr"));
+        let free = syn::Ident::from(kind.boxed_vec_function("free"));
         my_quote! {
             #[no_mangle]
-            pub unsafe extern fn __wbindgen_boxed_str_len(ptr: *mut String) -> usize {
+            pub unsafe extern fn #len(ptr: *mut Vec<#elem>) -> usize {
                 (*ptr).len()
             }
 
             #[no_mangle]
-            pub unsafe extern fn __wbindgen_boxed_str_ptr(ptr: *mut String) -> *const u8 {
+            pub unsafe extern fn #ptr(ptr: *mut Vec<#elem>) -> *const #elem {
                 (*ptr).as_ptr()
             }
 
             #[no_mangle]
-            pub unsafe extern fn __wbindgen_boxed_str_free(ptr: *mut String) {
+            pub unsafe extern fn #free(ptr: *mut Vec<#elem>) {
                 drop(Box::from_raw(ptr));
             }
         }
@@ -371,21 +780,110 @@ fn bindgen(export_name: &syn::LitStr,
         }
     };
 
+    let global_argument = if global_argument {
+        my_quote! {
+            #[no_mangle]
+            pub unsafe extern fn __wbindgen_global_argument_ptr() -> *mut u32 {
+                ::wasm_bindgen::__rt::GLOBAL_ARGUMENT.as_mut_ptr()
+            }
+        }
+    } else {
+        my_quote! {
+        }
+    };
+
+    let call = match receiver {
+        Receiver::StructFieldGet(_, field) => {
+            my_quote! { me.borrow().#field.clone() }
+        }
+        Receiver::StructFieldSet(_, field) => {
+            let val = &converted_arguments[0];
+            my_quote! { me.borrow_mut().#field = #val }
+        }
+        _ => my_quote! { #receiver(#(#converted_arguments),*) },
+    };
+
     let tokens = my_quote! {
         #malloc
+        #(#vec_mallocs)*
         #boxed_str
+        #boxed_vec
+        #global_argument
 
         #[export_name = #export_name]
         #[allow(non_snake_case)]
         pub extern fn #generated_name(#(#args),*) #ret_ty {
             #(#arg_conversions)*
-            let #ret = #receiver(#(#converted_arguments),*);
+            let #ret = #call;
+            #catch_err
             #convert_ret
         }
     };
     tokens.to_tokens(into);
 }
 
+fn malloc_tokens() -> Tokens {
+    my_quote! {
+        #[no_mangle]
+        pub extern fn __wbindgen_malloc(size: usize) -> *mut u8 {
+            // Any malloc request this big is bogus anyway. If this actually
+            // goes down to `Vec` we trigger a whole bunch of panicking
+            // machinery to get pulled in from libstd anyway as it'll verify
+            // the size passed in below.
+            //
+            // Head this all off by just aborting on too-big sizes. This
+            // avoids panicking (code bloat) and gives a better error
+            // message too hopefully.
+            if size >= usize::max_value() / 2 {
+                ::wasm_bindgen::throw("invalid malloc request");
+            }
+            let mut ret = Vec::with_capacity(size);
+            let ptr = ret.as_mut_ptr();
+            ::std::mem::forget(ret);
+            return ptr
+        }
+
+        #[no_mangle]
+        pub unsafe extern fn __wbindgen_free(ptr: *mut u8, size: usize) {
+            drop(Vec::<u8>::from_raw_parts(ptr, 0, size));
+        }
+    }
+}
+
+/// Returns the `__wbindgen_malloc_*` and `__wbindgen_free_*` exports for
+/// vectors of `elem`, or `None` if they've already been generated.
+///
+/// These allocate through a `Vec` of the element type itself so the memory is
+/// suitably aligned and is freed with the same layout it was allocated with,
+/// even when the `Vec` is rebuilt with `Vec::from_raw_parts`.
+fn vec_malloc_tokens(elem: syn::Ident) -> Option<Tokens> {
+    let kind = ast::vector_kind(elem);
+    let bit = 1 << (kind as usize);
+    if VEC_MALLOC_GENERATED.fetch_or(bit, Ordering::SeqCst) & bit != 0 {
+        return None
+    }
+    let malloc = syn::Ident::from(kind.malloc_function());
+    let free = syn::Ident::from(kind.free_function());
+    Some(my_quote! {
+        #[no_mangle]
+        pub extern fn #malloc(len: usize) -> *mut #elem {
+            // See `__wbindgen_malloc` for why oversized requests abort here
+            if len >= usize::max_value() / 2 / ::std::mem::size_of::<#elem>() {
+                ::wasm_bindgen::throw("invalid malloc request");
+            }
+            let mut ret = Vec::<#elem>::with_capacity(len);
+            let ptr = ret.as_mut_ptr();
+            ::std::mem::forget(ret);
+            return ptr
+        }
+
+        #[no_mangle]
+        pub unsafe extern fn #free(ptr: *mut #elem, len: usize) {
+            drop(Vec::<#elem>::from_raw_parts(ptr, 0, len));
+        }
+    })
+}
+
 impl ToTokens for Receiver {
     fn to_tokens(&self, tokens: &mut Tokens) {
         match *self {
@@ -411,6 +909,15 @@ impl ToTokens for Receiver {
                 syn::token::Dot::default().to_tokens(tokens);
                 name.to_tokens(tokens);
             }
+            Receiver::StructConsumingMethod(_, name) => {
+                (my_quote! { me }).to_tokens(tokens);
+                syn::token::Dot::default().to_tokens(tokens);
+                name.to_tokens(tokens);
+            }
+            Receiver::StructFieldGet(..) |
+            Receiver::StructFieldSet(..) => {
+                panic!("field accessors aren't function calls")
+            }
         }
     }
 }
@@ -426,6 +933,7 @@ fn bindgen_import(import: &ast::Import, tokens: &mut Tokens) {
     let mut abi_arguments = Vec::new();
     let mut arg_conversions = Vec::new();
     let ret_ident = syn::Ident::from("_ret");
+    let mut malloc = false;
 
     let names = import.decl.inputs
         .iter()
@@ -456,6 +964,23 @@ fn bindgen_import(import: &ast::Import, tokens: &mut Tokens) {
                 abi_arguments.push(my_quote! { #name: #i });
                 arg_conversions.push(my_quote! {});
             }
+            ast::Type::Char => {
+                abi_argument_names.push(name);
+                abi_arguments.push(my_quote! { #name: u32 });
+                arg_conversions.push(my_quote! { let #name = #name as u32; });
+            }
+            ast::Type::Integer64(_) => {
+                let lo = syn::Ident::from(format!("{}_lo", name));
+                let hi = syn::Ident::from(format!("{}_hi", name));
+                abi_argument_names.push(lo);
+                abi_argument_names.push(hi);
+                abi_arguments.push(my_quote! { #lo: u32 });
+                abi_arguments.push(my_quote! { #hi: u32 });
+                arg_conversions.push(my_quote! {
+                    let #lo = #name as u64 as u32;
+                    let #hi = (#name as u64 >> 32) as u32;
+                });
+            }
             ast::Type::Boolean => {
                 abi_argument_names.push(name);
                 abi_arguments.push(my_quote! { #name: u32 });
@@ -471,7 +996,8 @@ fn bindgen_import(import: &ast::Import, tokens: &mut Tokens) {
                 abi_arguments.push(my_quote! { #name: *const #i });
                 arg_conversions.push(my_quote! {});
             }
-            ast::Type::BorrowedStr => {
+            ast::Type::BorrowedStr |
+            ast::Type::String => {
                 let ptr = syn::Ident::from(format!("{}_ptr", name));
                 let len = syn::Ident::from(format!("{}_len", name));
                 abi_argument_names.push(ptr);
@@ -483,6 +1009,31 @@ fn bindgen_import(import: &ast::Import, tokens: &mut Tokens) {
                     let #len = #name.len();
                 });
             }
+            ast::Type::BorrowedSlice(elem) |
+            ast::Type::Vector(elem) => {
+                let ptr = syn::Ident::from(format!("{}_ptr", name));
+                let len = syn::Ident::from(format!("{}_len", name));
+                abi_argument_names.push(ptr);
+                abi_argument_names.push(len);
+                abi_arguments.push(my_quote! { #ptr: *const #elem });
+                abi_arguments.push(my_quote! { #len: usize });
+                arg_conversions.push(my_quote! {
+                    let #ptr = #name.as_ptr();
+                    let #len = #name.len();
+                });
+            }
+            ast::Type::BorrowedMutSlice(elem) => {
+                let ptr = syn::Ident::from(format!("{}_ptr", name));
+                let len = syn::Ident::from(format!("{}_len", name));
+                abi_argument_names.push(ptr);
+                abi_argument_names.push(len);
+                abi_arguments.push(my_quote! { #ptr: *mut #elem });
+                abi_arguments.push(my_quote! { #len: usize });
+                arg_conversions.push(my_quote! {
+                    let #ptr = #name.as_mut_ptr();
+                    let #len = #name.len();
+                });
+            }
             ast::Type::JsObject => {
                 abi_argument_names.push(name);
                 abi_arguments.push(my_quote! { #name: u32 });
@@ -497,61 +1048,499 @@ fn bindgen_import(import: &ast::Import, tokens: &mut Tokens) {
                     let #name = ::wasm_bindgen::JsObject::__get_idx(#name);
                 });
             }
-            ast::Type::String => panic!("can't use `String` in foreign functions"),
-            ast::Type::ByValue(_name) |
-            ast::Type::ByRef(_name) |
-            ast::Type::ByMutRef(_name) => {
-                panic!("can't use struct types in foreign functions yet");
+            ast::Type::ByValue(class) => {
+                abi_argument_names.push(name);
+                abi_arguments.push(my_quote! {
+                    #name: *mut ::wasm_bindgen::__rt::WasmRefCell<#class>
+                });
+                arg_conversions.push(my_quote! {
+                    let #name = Box::into_raw(Box::new(
+                        ::wasm_bindgen::__rt::WasmRefCell::new(#name)
+                    ));
+                });
+            }
+            // Borrowed structs don't live in a `WasmRefCell` box, so there's
+            // no pointer we could hand out to JS for them.
+            ast::Type::ByRef(class) |
+            ast::Type::ByMutRef(class) => {
+                panic!("can't pass borrowed structs (`&{0}` or `&mut {0}`) to \
+                        foreign functions, pass them by value instead",
+                       class);
+            }
+            ast::Type::Option(ref t) => {
+                match **t {
+                    ast::Type::Integer(i) => {
+                        let some = syn::Ident::from(format!("{}_some", name));
+                        abi_argument_names.push(some);
+                        abi_argument_names.push(name);
+                        abi_arguments.push(my_quote! { #some: u32 });
+                        abi_arguments.push(my_quote! { #name: #i });
+                        arg_conversions.push(my_quote! {
+                            let (#some, #name) = match #name {
+                                Some(val) => (1, val),
+                                None => (0, 0 as #i),
+                            };
+                        });
+                    }
+                    ast::Type::Boolean => {
+                        abi_argument_names.push(name);
+                        abi_arguments.push(my_quote! { #name: u32 });
+                        arg_conversions.push(my_quote! {
+                            let #name = match #name {
+                                Some(b) => b as u32,
+                                None => 2,
+                            };
+                        });
+                    }
+                    ast::Type::BorrowedStr |
+                    ast::Type::String => {
+                        let ptr = syn::Ident::from(format!("{}_ptr", name));
+                        let len = syn::Ident::from(format!("{}_len", name));
+                        abi_argument_names.push(ptr);
+                        abi_argument_names.push(len);
+                        abi_arguments.push(my_quote! { #ptr: *const u8 });
+                        abi_arguments.push(my_quote! { #len: usize });
+                        arg_conversions.push(my_quote! {
+                            let (#ptr, #len) = match #name {
+                                Some(ref s) => (s.as_ptr(), s.len()),
+                                None => (::std::ptr::null(), 0),
+                            };
+                        });
+                    }
+                    ast::Type::JsObject => {
+                        abi_argument_names.push(name);
+                        abi_arguments.push(my_quote! { #name: u32 });
+                        arg_conversions.push(my_quote! {
+                            let #name = match #name {
+                                Some(obj) => ::wasm_bindgen::JsObject::__into_idx(obj),
+                                None => u32::max_value(),
+                            };
+                        });
+                    }
+                    ast::Type::ByValue(class) => {
+                        abi_argument_names.push(name);
+                        abi_arguments.push(my_quote! {
+                            #name: *mut ::wasm_bindgen::__rt::WasmRefCell<#class>
+                        });
+                        arg_conversions.push(my_quote! {
+                            let #name = match #name {
+                                Some(val) => {
+                                    Box::into_raw(Box::new(
+                                        ::wasm_bindgen::__rt::WasmRefCell::new(val)
+                                    ))
+                                }
+                                None => ::std::ptr::null_mut(),
+                            };
+                        });
+                    }
+                    _ => panic!("unsupported type inside of `Option`"),
+                }
+            }
+            ast::Type::Result(_) => panic!("`Result` can only be returned"),
+            ast::Type::Enum(_) => {
+                abi_argument_names.push(name);
+                abi_arguments.push(my_quote! { #name: u32 });
+                arg_conversions.push(my_quote! { let #name = #name as u32; });
+            }
+            ast::Type::ImportedType(_) => {
+                abi_argument_names.push(name);
+                abi_arguments.push(my_quote! { #name: u32 });
+                arg_conversions.push(my_quote! {
+                    let #name = ::wasm_bindgen::JsObject::__into_idx(#name.obj);
+                });
+            }
+            ast::Type::ImportedTypeRef(_) => {
+                abi_argument_names.push(name);
+                abi_arguments.push(my_quote! { #name: u32 });
+                arg_conversions.push(my_quote! {
+                    let #name = ::wasm_bindgen::JsObject::__get_idx(&#name.obj);
+                });
+            }
+            ast::Type::Closure(ref c) => {
+                // Closures are passed as the two halves of their trait object
+                // pointer, which JS hands back to the trampoline to call them.
+                let a = syn::Ident::from(format!("{}_a", name));
+                let b = syn::Ident::from(format!("{}_b", name));
+                abi_argument_names.push(a);
+                abi_argument_names.push(b);
+                abi_arguments.push(my_quote! { #a: u32 });
+                abi_arguments.push(my_quote! { #b: u32 });
+                if c.owned {
+                    let idx = syn::Ident::from(format!("{}_idx", name));
+                    abi_argument_names.push(idx);
+                    abi_arguments.push(my_quote! { #idx: *mut u32 });
+                    arg_conversions.push(my_quote! {
+                        let #idx = #name.__js_idx_ptr();
+                        let (#a, #b): (u32, u32) = ::std::mem::transmute(#name.__raw());
+                    });
+                } else {
+                    arg_conversions.push(my_quote! {
+                        let (#a, #b): (u32, u32) = ::std::mem::transmute(#name);
+                    });
+                }
+                bindgen_closure_trampoline(c, tokens);
             }
         }
     }
+    // With `catch` an exception thrown in JS is written to `_exn_data` and
+    // returned as an `Err`, otherwise the return value is converted as usual.
+    let ret_type = match import.function.ret {
+        Some(ast::Type::Result(ref t)) if import.catch => t.as_ref().map(|t| &**t),
+        Some(ast::Type::Result(_)) => {
+            panic!("foreign functions returning `Result` must be marked \
+                    with `#[wasm_bindgen(catch)]`");
+        }
+        _ if import.catch => {
+            panic!("foreign functions marked with `#[wasm_bindgen(catch)]` \
+                    must return `Result<T, JsObject>`");
+        }
+        ref ret => ret.as_ref(),
+    };
     let abi_ret;
-    let convert_ret;
-    match import.function.ret {
-        Some(ast::Type::Integer(i)) => {
+    let mut convert_ret;
+    match ret_type {
+        Some(&ast::Type::Integer(i)) => {
             abi_ret = my_quote! { #i };
             convert_ret = my_quote! { #ret_ident };
         }
-        Some(ast::Type::Boolean) => {
+        Some(&ast::Type::Char) => {
+            abi_ret = my_quote! { u32 };
+            convert_ret = my_quote! {
+                match ::std::char::from_u32(#ret_ident) {
+                    Some(c) => c,
+                    None => ::wasm_bindgen::throw("invalid `char` returned from JS"),
+                }
+            };
+        }
+        Some(&ast::Type::Integer64(i)) => {
+            let hi_ptr = syn::Ident::from("_ret_hi_ptr");
+            abi_argument_names.push(hi_ptr);
+            abi_arguments.push(my_quote! { #hi_ptr: *mut u32 });
+            arg_conversions.push(my_quote! {
+                let mut _ret_hi = 0;
+                let #hi_ptr = &mut _ret_hi as *mut u32;
+            });
+            abi_ret = my_quote! { u32 };
+            convert_ret = my_quote! {
+                (((_ret_hi as u64) << 32) | #ret_ident as u64) as #i
+            };
+        }
+        Some(&ast::Type::Boolean) => {
             abi_ret = my_quote! { u32 };
             convert_ret = my_quote! { #ret_ident != 0 };
         }
-        Some(ast::Type::RawConstPtr(i)) => {
+        Some(&ast::Type::RawConstPtr(i)) => {
             abi_ret = my_quote! { *const #i };
             convert_ret = my_quote! { #ret_ident };
         }
-        Some(ast::Type::RawMutPtr(i)) => {
+        Some(&ast::Type::RawMutPtr(i)) => {
             abi_ret = my_quote! { *mut #i };
             convert_ret = my_quote! { #ret_ident };
         }
-        Some(ast::Type::JsObject) => {
+        Some(&ast::Type::JsObject) => {
             abi_ret = my_quote! { u32 };
             convert_ret = my_quote! {
                 ::wasm_bindgen::JsObject::__from_idx(#ret_ident)
             };
         }
-        Some(ast::Type::JsObjectRef) => panic!("can't return a borrowed ref"),
-        Some(ast::Type::BorrowedStr) => panic!("can't return a borrowed string"),
-        Some(ast::Type::ByRef(_)) => panic!("can't return a borrowed ref"),
-        Some(ast::Type::ByMutRef(_)) => panic!("can't return a borrowed ref"),
-        Some(ast::Type::String) => panic!("can't return a string in foreign functions"),
-        Some(ast::Type::ByValue(_)) => panic!("can't return a struct in a foreign function"),
+        Some(&ast::Type::Vector(elem)) => {
+            if let Some(t) = vec_malloc_tokens(elem) {
+                t.to_tokens(tokens);
+            }
+            let len_ptr = syn::Ident::from("_ret_len_ptr");
+            abi_argument_names.push(len_ptr);
+            abi_arguments.push(my_quote! { #len_ptr: *mut usize });
+            arg_conversions.push(my_quote! {
+                let mut _ret_len = 0;
+                let #len_ptr = &mut _ret_len as *mut usize;
+            });
+            abi_ret = my_quote! { *mut #elem };
+            convert_ret = my_quote! {
+                ::std::vec::Vec::from_raw_parts(#ret_ident, _ret_len, _ret_len)
+            };
+        }
+        Some(&ast::Type::String) => {
+            malloc = !MALLOC_GENERATED.swap(true, Ordering::SeqCst);
+            let len_ptr = syn::Ident::from("_ret_len_ptr");
+            abi_argument_names.push(len_ptr);
+            abi_arguments.push(my_quote! { #len_ptr: *mut usize });
+            arg_conversions.push(my_quote! {
+                let mut _ret_len = 0;
+                let #len_ptr = &mut _ret_len as *mut usize;
+            });
+            abi_ret = my_quote! { *mut u8 };
+            convert_ret = my_quote! {
+                let vec = ::std::vec::Vec::from_raw_parts(#ret_ident, _ret_len, _ret_len);
+                ::std::string::String::from_utf8_unchecked(vec)
+            };
+        }
+        Some(&ast::Type::JsObjectRef) => panic!("can't return a borrowed ref"),
+        Some(&ast::Type::BorrowedStr) => panic!("can't return a borrowed string"),
+        Some(&ast::Type::BorrowedSlice(_)) |
+        Some(&ast::Type::BorrowedMutSlice(_)) => panic!("can't return a borrowed slice"),
+        Some(&ast::Type::ByRef(_)) => panic!("can't return a borrowed ref"),
+        Some(&ast::Type::ByMutRef(_)) => panic!("can't return a borrowed ref"),
+        Some(&ast::Type::ByValue(class)) => {
+            abi_ret = my_quote! { *mut ::wasm_bindgen::__rt::WasmRefCell<#class> };
+            convert_ret = my_quote! {
+                ::wasm_bindgen::__rt::assert_not_null(#ret_ident);
+                (*#ret_ident).borrow_mut();
+                Box::from_raw(#ret_ident).into_inner()
+            };
+        }
+        Some(&ast::Type::Option(ref t)) => {
+            match **t {
+                ast::Type::Integer(i) => {
+                    let some_ptr = syn::Ident::from("_ret_some_ptr");
+                    abi_argument_names.push(some_ptr);
+                    abi_arguments.push(my_quote! { #some_ptr: *mut u32 });
+                    arg_conversions.push(my_quote! {
+                        let mut _ret_some = 0;
+                        let #some_ptr = &mut _ret_some as *mut u32;
+                    });
+                    abi_ret = my_quote! { #i };
+                    convert_ret = my_quote! {
+                        if _ret_some != 0 { Some(#ret_ident) } else { None }
+                    };
+                }
+                ast::Type::Boolean => {
+                    abi_ret = my_quote! { u32 };
+                    convert_ret = my_quote! {
+                        match #ret_ident {
+                            0 => Some(false),
+                            1 => Some(true),
+                            _ => None,
+                        }
+                    };
+                }
+                ast::Type::String => {
+                    malloc = !MALLOC_GENERATED.swap(true, Ordering::SeqCst);
+                    let len_ptr = syn::Ident::from("_ret_len_ptr");
+                    abi_argument_names.push(len_ptr);
+                    abi_arguments.push(my_quote! { #len_ptr: *mut usize });
+                    arg_conversions.push(my_quote! {
+                        let mut _ret_len = 0;
+                        let #len_ptr = &mut _ret_len as *mut usize;
+                    });
+                    abi_ret = my_quote! { *mut u8 };
+                    convert_ret = my_quote! {
+                        if #ret_ident.is_null() {
+                            None
+                        } else {
+                            let vec = ::std::vec::Vec::from_raw_parts(#ret_ident, _ret_len, _ret_len);
+                            Some(::std::string::String::from_utf8_unchecked(vec))
+                        }
+                    };
+                }
+                ast::Type::JsObject => {
+                    abi_ret = my_quote! { u32 };
+                    convert_ret = my_quote! {
+                        if #ret_ident == u32::max_value() {
+                            None
+                        } else {
+                            Some(::wasm_bindgen::JsObject::__from_idx(#ret_ident))
+                        }
+                    };
+                }
+                ast::Type::ByValue(class) => {
+                    abi_ret = my_quote! { *mut ::wasm_bindgen::__rt::WasmRefCell<#class> };
+                    convert_ret = my_quote! {
+                        if #ret_ident.is_null() {
+                            None
+                        } else {
+                            (*#ret_ident).borrow_mut();
+                            Some(Box::from_raw(#ret_ident).into_inner())
+                        }
+                    };
+                }
+                ast::Type::BorrowedStr => panic!("can't return a borrowed string"),
+                _ => panic!("unsupported type inside of `Option`"),
+            }
+        }
+        Some(&ast::Type::Result(_)) => unreachable!(),
+        Some(&ast::Type::Enum(name)) => {
+            abi_ret = my_quote! { u32 };
+            convert_ret = my_quote! { #name::__wbindgen_from_u32(#ret_ident) };
+        }
+        Some(&ast::Type::ImportedType(name)) => {
+            abi_ret = my_quote! { u32 };
+            convert_ret = my_quote! {
+                #name { obj: ::wasm_bindgen::JsObject::__from_idx(#ret_ident) }
+            };
+        }
+        Some(&ast::Type::ImportedTypeRef(_)) => panic!("can't return a borrowed ref"),
+        Some(&ast::Type::Closure(_)) => panic!("can't return a closure"),
         None => {
             abi_ret = my_quote! { () };
             convert_ret = my_quote! {};
         }
     }
 
-    (quote! {
+    if import.catch {
+        let exn_data_ptr = syn::Ident::from("_exn_data_ptr");
+        abi_argument_names.push(exn_data_ptr);
+        abi_arguments.push(my_quote! { #exn_data_ptr: *mut u32 });
+        arg_conversions.push(my_quote! {
+            let mut _exn_data = [0; 2];
+            let #exn_data_ptr = _exn_data.as_mut_ptr();
+        });
+        convert_ret = my_quote! {
+            if _exn_data[0] == 1 {
+                return Err(::wasm_bindgen::JsObject::__from_idx(_exn_data[1]))
+            }
+            Ok({ #convert_ret })
+        };
+    }
+
+    if malloc {
+        malloc_tokens().to_tokens(tokens);
+    }
+
+    // Imported methods take their receiver explicitly as the first argument,
+    // which is turned into `&self` here.
+    let receiver;
+    let arguments = if import.method {
+        let mut inputs = import.decl.inputs.iter();
+        let this = match inputs.next() {
+            Some(&syn::FnArg::Captured(ref c)) => &c.pat,
+            _ => unreachable!(),
+        };
+        receiver = my_quote! { let #this = self; };
+        let rest = inputs.collect::<Vec<_>>();
+        my_quote! { &self, #(#rest),* }
+    } else {
+        receiver = my_quote! {};
+        my_quote! { #arguments }
+    };
+    let shim_name = import.shim_name();
+
+    let func = quote! {
         #vis #fn_token #name(#arguments) #ret {
+            #receiver
             extern {
-                fn #name(#(#abi_arguments),*) -> #abi_ret;
+                fn #shim_name(#(#abi_arguments),*) -> #abi_ret;
             }
             unsafe {
                 #(#arg_conversions)*
-                let #ret_ident = #name(#(#abi_argument_names),*);
+                let #ret_ident = #shim_name(#(#abi_argument_names),*);
                 #convert_ret
             }
         }
-    }).to_tokens(tokens);
+    };
+    match import.class {
+        Some(class) => {
+            (my_quote! {
+                impl #class {
+                    #func
+                }
+            }).to_tokens(tokens);
+        }
+        None => func.to_tokens(tokens),
+    }
+
+    // Imported statics are fetched through the getter generated above the
+    // first time they're accessed.
+    if let Some(ref global) = import.global {
+        let vis = &global.vis;
+        let global_name = global.name;
+        let ty = &global.ty;
+        (my_quote! {
+            #[allow(non_upper_case_globals)]
+            #vis static #global_name: ::wasm_bindgen::JsStatic<#ty> = {
+                thread_local!(static INIT: &'static #ty = unsafe {
+                    &*::std::boxed::Box::into_raw(::std::boxed::Box::new(#name()))
+                });
+                ::wasm_bindgen::JsStatic { __inner: &INIT }
+            };
+        }).to_tokens(tokens);
+    }
+}
+
+fn bindgen_closure_trampoline(c: &ast::Closure, into: &mut Tokens) {
+    let trampoline = c.trampoline();
+    let fresh = CLOSURES_GENERATED.with(|set| {
+        set.borrow_mut().insert(trampoline.as_ref().to_string())
+    });
+    if !fresh {
+        return
+    }
+
+    let mut args = Vec::new();
+    let mut arg_conversions = Vec::new();
+    let mut names = Vec::new();
+    for (i, ty) in c.arguments.iter().enumerate() {
+        let name = syn::Ident::from(format!("arg{}", i));
+        match *ty {
+            ast::Type::Integer(n) => {
+                args.push(my_quote! { #name: #n });
+            }
+            ast::Type::Boolean => {
+                args.push(my_quote! { #name: u32 });
+                arg_conversions.push(my_quote! { let #name = #name != 0; });
+            }
+            ast::Type::JsObject => {
+                args.push(my_quote! { #name: u32 });
+                arg_conversions.push(my_quote! {
+                    let #name = ::wasm_bindgen::JsObject::__from_idx(#name);
+                });
+            }
+            _ => unreachable!(),
+        }
+        names.push(name);
+    }
+    let (ret_ty, convert_ret) = match c.ret {
+        Some(ast::Type::Integer(i)) => (my_quote! { -> #i }, my_quote! { ret }),
+        Some(ast::Type::Boolean) => (my_quote! { -> u32 }, my_quote! { ret as u32 }),
+        Some(ast::Type::JsObject) => {
+            (my_quote! { -> u32 }, my_quote! { ::wasm_bindgen::JsObject::__into_idx(ret) })
+        }
+        Some(_) => unreachable!(),
+        None => (my_quote! {}, my_quote! { ret }),
+    };
+    let ty = &c.ty;
+    let ptr = if c.mutable {
+        my_quote! { *mut #ty }
+    } else {
+        my_quote! { *const #ty }
+    };
+
+    (my_quote! {
+        #[no_mangle]
+        pub unsafe extern fn #trampoline(a: u32, b: u32, #(#args),*) #ret_ty {
+            let f: #ptr = ::std::mem::transmute((a, b));
+            #(#arg_conversions)*
+            let ret = (*f)(#(#names),*);
+            #convert_ret
+        }
+    }).to_tokens(into);
+}
+
+fn bindgen_imported_type(t: &ast::ImportedType, into: &mut Tokens) {
+    let vis = &t.vis;
+    let name = t.name;
+    let attrs = &t.attrs;
+    (my_quote! {
+        #(#attrs)*
+        #vis struct #name {
+            obj: ::wasm_bindgen::JsObject,
+        }
+
+        impl From<::wasm_bindgen::JsObject> for #name {
+            fn from(obj: ::wasm_bindgen::JsObject) -> #name {
+                #name { obj }
+            }
+        }
+
+        impl From<#name> for ::wasm_bindgen::JsObject {
+            fn from(t: #name) -> ::wasm_bindgen::JsObject {
+                t.obj
+            }
+        }
+
+        impl AsRef<::wasm_bindgen::JsObject> for #name {
+            fn as_ref(&self) -> &::wasm_bindgen::JsObject {
+                &self.obj
+            }
+        }
+    }).to_tokens(into);
 }
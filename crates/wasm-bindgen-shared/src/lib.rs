@@ -5,7 +5,9 @@ extern crate serde_derive;
 pub struct Program {
     pub structs: Vec<Struct>,
     pub free_functions: Vec<Function>,
-    pub imports: Vec<Function>,
+    pub imports: Vec<Import>,
+    pub enums: Vec<Enum>,
+    pub consts: Vec<Const>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -13,15 +15,63 @@ pub struct Struct {
     pub name: String,
     pub functions: Vec<Function>,
     pub methods: Vec<Method>,
+    pub fields: Vec<StructField>,
+    pub constructor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct StructField {
+    pub name: String,
+    pub ty: Type,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Method {
     pub mutable: bool,
+    pub consume: bool,
     pub function: Function,
 }
 
 #[derive(Serialize, Deserialize)]
+pub struct Enum {
+    pub name: String,
+    pub variants: Vec<EnumVariant>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EnumVariant {
+    pub name: String,
+    pub value: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Const {
+    pub name: String,
+    pub value: ConstValue,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub enum ConstValue {
+    Number(f64),
+    Boolean(bool),
+    String(String),
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Import {
+    pub function: Function,
+    pub catch: bool,
+    pub class: Option<String>,
+    pub method: bool,
+    pub constructor: bool,
+    /// Whether this is a `static` whose value is read from the imports
+    /// rather than a function which is called.
+    pub global: bool,
+    /// JS module this is imported from, if not from the `imports` object.
+    pub module: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Function {
     pub name: String,
     pub arguments: Vec<Type>,
@@ -37,6 +87,47 @@ impl Struct {
         name.push_str("_free");
         return name
     }
+
+    pub fn field_getter(&self, field: &str) -> String {
+        let mut name = format!("__wbindgen_");
+        name.extend(self.name
+            .chars()
+            .flat_map(|s| s.to_lowercase()));
+        name.push_str("_get_");
+        name.push_str(field);
+        return name
+    }
+
+    pub fn field_setter(&self, field: &str) -> String {
+        let mut name = format!("__wbindgen_");
+        name.extend(self.name
+            .chars()
+            .flat_map(|s| s.to_lowercase()));
+        name.push_str("_set_");
+        name.push_str(field);
+        return name
+    }
+}
+
+impl Import {
+    /// Name of the wasm import through which this function is called.
+    ///
+    /// Functions belonging to an imported class are prefixed with the name of
+    /// the class as, for example, many classes can have a `new` constructor.
+    pub fn shim_name(&self) -> String {
+        match self.class {
+            Some(ref class) => {
+                let mut name = class
+                    .chars()
+                    .flat_map(|s| s.to_lowercase())
+                    .collect::<String>();
+                name.push_str("_");
+                name.push_str(&self.function.name);
+                name
+            }
+            None => self.function.name.clone(),
+        }
+    }
 }
 
 impl Function {
@@ -55,17 +146,50 @@ impl Function {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub enum Type {
     Number,
+    /// A 64-bit integer, passed through wasm as two `u32` halves
+    I64,
+    U64,
+    /// A `char`, passed through wasm as its `u32` code point
+    Char,
     BorrowedStr,
     String,
+    BorrowedSlice(VectorKind),
+    BorrowedMutSlice(VectorKind),
+    Vector(VectorKind),
     ByValue(String),
     ByRef(String),
     ByMutRef(String),
     JsObject,
     JsObjectRef,
     Boolean,
+    Option(Box<Type>),
+    Result(Option<Box<Type>>),
+    Enum(String),
+    Closure(Box<Closure>),
+}
+
+/// A closure passed to an imported function. The `function` describes its
+/// signature and is named after the export JS calls to invoke it.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Closure {
+    pub mutable: bool,
+    pub owned: bool,
+    pub function: Function,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub enum VectorKind {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
 }
 
 impl Type {
@@ -76,3 +200,59 @@ impl Type {
         }
     }
 }
+
+impl VectorKind {
+    pub fn from_rust(name: &str) -> Option<VectorKind> {
+        match name {
+            "i8" => Some(VectorKind::I8),
+            "u8" => Some(VectorKind::U8),
+            "i16" => Some(VectorKind::I16),
+            "u16" => Some(VectorKind::U16),
+            "i32" => Some(VectorKind::I32),
+            "u32" => Some(VectorKind::U32),
+            "f32" => Some(VectorKind::F32),
+            "f64" => Some(VectorKind::F64),
+            _ => None,
+        }
+    }
+
+    pub fn rust_name(&self) -> &'static str {
+        match *self {
+            VectorKind::I8 => "i8",
+            VectorKind::U8 => "u8",
+            VectorKind::I16 => "i16",
+            VectorKind::U16 => "u16",
+            VectorKind::I32 => "i32",
+            VectorKind::U32 => "u32",
+            VectorKind::F32 => "f32",
+            VectorKind::F64 => "f64",
+        }
+    }
+
+    /// Size, in bytes, of one element of this kind.
+    pub fn size(&self) -> usize {
+        match *self {
+            VectorKind::I8 | VectorKind::U8 => 1,
+            VectorKind::I16 | VectorKind::U16 => 2,
+            VectorKind::I32 | VectorKind::U32 | VectorKind::F32 => 4,
+            VectorKind::F64 => 8,
+        }
+    }
+
+    /// Name of the exported function operating on a boxed `Vec` of this kind,
+    /// for example `__wbindgen_boxed_vec_f64_len` for a `suffix` of `len`.
+    pub fn boxed_vec_function(&self, suffix: &str) -> String {
+        format!("__wbindgen_boxed_vec_{}_{}", self.rust_name(), suffix)
+    }
+
+    /// Name of the export allocating space for a number of elements of this
+    /// kind, for example `__wbindgen_malloc_f64`.
+    pub fn malloc_function(&self) -> String {
+        format!("__wbindgen_malloc_{}", self.rust_name())
+    }
+
+    /// Name of the export freeing space allocated by `malloc_function`.
+    pub fn free_function(&self) -> String {
+        format!("__wbindgen_free_{}", self.rust_name())
+    }
+}
@@ -7,9 +7,16 @@
 #![feature(use_extern_macros)]
 
 extern crate wasm_bindgen_macro;
+#[cfg(feature = "serde-serialize")]
+extern crate serde;
+#[cfg(feature = "serde-serialize")]
+extern crate serde_json;
 
+use std::cell::UnsafeCell;
 use std::mem;
+use std::ops::Deref;
 use std::ptr;
+use std::thread::LocalKey;
 
 /// A module which is typically glob imported from:
 ///
@@ -19,6 +26,8 @@ use std::ptr;
 pub mod prelude {
     pub use wasm_bindgen_macro::wasm_bindgen;
     pub use JsObject;
+    pub use JsArray;
+    pub use Closure;
 }
 
 /// Representation of an object owned by JS.
@@ -62,6 +71,21 @@ impl JsObject {
         }
     }
 
+    /// Creates a new JS value by serializing `t` to JSON and parsing the
+    /// result with `JSON.parse` in JS.
+    ///
+    /// This requires the `serde-serialize` feature of this crate. Returns an
+    /// error if `t` can't be serialized to JSON.
+    #[cfg(feature = "serde-serialize")]
+    pub fn from_serde<T>(t: &T) -> serde_json::Result<JsObject>
+        where T: serde::ser::Serialize + ?Sized,
+    {
+        let s = serde_json::to_string(t)?;
+        unsafe {
+            Ok(JsObject::__from_idx(__wbindgen_json_parse(s.as_ptr(), s.len())))
+        }
+    }
+
     /// Creates a new JS value representing `undefined`.
     pub fn undefined() -> JsObject {
         unsafe {
@@ -175,6 +199,216 @@ impl JsObject {
             __wbindgen_is_symbol(self.idx) == 1
         }
     }
+
+    /// Tests whether the type of this JS value is `object`, excluding `null`.
+    ///
+    /// Arrays are objects, but functions are not.
+    pub fn is_object(&self) -> bool {
+        unsafe {
+            __wbindgen_is_object(self.idx) == 1
+        }
+    }
+
+    /// Tests whether the type of this JS value is `function`
+    pub fn is_function(&self) -> bool {
+        unsafe {
+            __wbindgen_is_function(self.idx) == 1
+        }
+    }
+
+    /// Tests whether this JS value is an array, as determined by
+    /// `Array.isArray`.
+    pub fn is_array(&self) -> bool {
+        unsafe {
+            __wbindgen_is_array(self.idx) == 1
+        }
+    }
+
+    /// Returns the result of the JS `typeof` operator on this value, for
+    /// example `"number"` or `"object"`.
+    pub fn typeof_name(&self) -> String {
+        unsafe {
+            let mut len = 0;
+            let ptr = __wbindgen_typeof(self.idx, &mut len);
+            let data = Vec::from_raw_parts(ptr, len, len);
+            String::from_utf8_unchecked(data)
+        }
+    }
+
+    /// Tests whether this JS value is an instance of `class`, as if
+    /// `this instanceof class` had been evaluated in JS.
+    ///
+    /// Returns `false` if `class` isn't something `instanceof` accepts, such
+    /// as a value which isn't callable.
+    pub fn instanceof(&self, class: &JsObject) -> bool {
+        unsafe {
+            __wbindgen_instanceof(self.idx, class.idx) == 1
+        }
+    }
+
+    /// Compares this JS value with `other` using JS loose equality (`==`).
+    pub fn loose_eq(&self, other: &JsObject) -> bool {
+        unsafe {
+            __wbindgen_loose_eq(self.idx, other.idx) == 1
+        }
+    }
+
+    /// Compares this JS value with `other` using JS strict equality (`===`).
+    pub fn strict_eq(&self, other: &JsObject) -> bool {
+        unsafe {
+            __wbindgen_strict_eq(self.idx, other.idx) == 1
+        }
+    }
+
+    /// Returns the value of the property `key` of this JS value, as if
+    /// `this[key]` had been evaluated in JS.
+    ///
+    /// Missing properties yield `undefined`. Returns `Err` with the exception
+    /// if one was thrown, for example if this value is `null` or `undefined`
+    /// or a getter threw.
+    pub fn get<K: Into<JsObject>>(&self, key: K) -> Result<JsObject, JsObject> {
+        let key = key.into();
+        let idx = catch(|exn_data| unsafe {
+            __wbindgen_object_get(self.idx, key.idx, exn_data)
+        })?;
+        Ok(JsObject::__from_idx(idx))
+    }
+
+    /// Sets the property `key` of this JS value to `value`, as if
+    /// `this[key] = value` had been evaluated in JS.
+    ///
+    /// Returns `Err` with the exception if one was thrown, for example if
+    /// this value is `null` or `undefined` or a setter threw.
+    pub fn set<K: Into<JsObject>>(&self, key: K, value: &JsObject)
+        -> Result<(), JsObject>
+    {
+        let key = key.into();
+        catch(|exn_data| unsafe {
+            __wbindgen_object_set(self.idx, key.idx, value.idx, exn_data)
+        })?;
+        Ok(())
+    }
+
+    /// Tests whether this JS value has a property `key`, either its own or
+    /// inherited, as if `key in this` had been evaluated in JS.
+    ///
+    /// Returns `Err` with the exception if one was thrown, for example if
+    /// this value isn't an object.
+    pub fn has<K: Into<JsObject>>(&self, key: K) -> Result<bool, JsObject> {
+        let key = key.into();
+        let ret = catch(|exn_data| unsafe {
+            __wbindgen_object_has(self.idx, key.idx, exn_data)
+        })?;
+        Ok(ret == 1)
+    }
+
+    /// Deletes the property `key` of this JS value, returning whether the
+    /// deletion succeeded.
+    ///
+    /// Returns `Err` with the exception if one was thrown, for example if
+    /// this value is `null` or `undefined`.
+    pub fn delete<K: Into<JsObject>>(&self, key: K) -> Result<bool, JsObject> {
+        let key = key.into();
+        let ret = catch(|exn_data| unsafe {
+            __wbindgen_object_delete(self.idx, key.idx, exn_data)
+        })?;
+        Ok(ret == 1)
+    }
+
+    /// Returns the names of the own enumerable properties of this JS value,
+    /// as computed by `Object.keys`.
+    ///
+    /// Returns `Err` with the exception if one was thrown, for example if
+    /// this value is `null` or `undefined`.
+    pub fn keys(&self) -> Result<Vec<JsObject>, JsObject> {
+        let idx = catch(|exn_data| unsafe {
+            __wbindgen_object_keys(self.idx, exn_data)
+        })?;
+        let keys = JsArray { obj: JsObject::__from_idx(idx) };
+        Ok(keys.iter().collect())
+    }
+
+    /// Deserializes this JS value into `T` by converting it to JSON with
+    /// `JSON.stringify` in JS.
+    ///
+    /// This requires the `serde-serialize` feature of this crate. `undefined`
+    /// deserializes as JSON `null`. Returns an error if the value can't be
+    /// converted to JSON, for example if it's a function or contains a cycle,
+    /// or if the JSON doesn't match `T`.
+    #[cfg(feature = "serde-serialize")]
+    pub fn into_serde<T>(&self) -> serde_json::Result<T>
+        where T: serde::de::DeserializeOwned,
+    {
+        let mut len = 0;
+        let ptr = catch(|exn_data| unsafe {
+            __wbindgen_json_serialize(self.idx, &mut len, exn_data)
+        });
+        let ptr = match ptr {
+            Ok(ptr) => ptr,
+            Err(e) => {
+                let msg = e.get("message")
+                    .ok()
+                    .and_then(|m| m.as_string())
+                    .unwrap_or_else(|| "JSON.stringify threw".to_string());
+                return Err(serde::de::Error::custom(msg))
+            }
+        };
+        unsafe {
+            let data = Vec::from_raw_parts(ptr, len, len);
+            serde_json::from_slice(&data)
+        }
+    }
+
+    /// Calls this JS value as a function with no arguments.
+    ///
+    /// Returns the value the function returned, or `Err` with the exception
+    /// if it threw one (for example if this value isn't a function).
+    pub fn call0(&self) -> Result<JsObject, JsObject> {
+        self.call(u32::max_value(), &[])
+    }
+
+    /// Calls this JS value as a function with one argument.
+    ///
+    /// Returns the value the function returned, or `Err` with the exception
+    /// if it threw one.
+    pub fn call1(&self, arg: &JsObject) -> Result<JsObject, JsObject> {
+        self.call(u32::max_value(), &[arg.idx])
+    }
+
+    /// Calls this JS value as a function with `this` as the receiver and the
+    /// arguments `args`.
+    ///
+    /// Returns the value the function returned, or `Err` with the exception
+    /// if it threw one.
+    pub fn call_with(&self, this: &JsObject, args: &[JsObject])
+        -> Result<JsObject, JsObject>
+    {
+        let args = args.iter().map(|a| a.idx).collect::<Vec<_>>();
+        self.call(this.idx, &args)
+    }
+
+    /// Calls this value with the receiver `this`, where `u32::max_value()`
+    /// means `undefined`, and the indices of the arguments.
+    fn call(&self, this: u32, args: &[u32]) -> Result<JsObject, JsObject> {
+        let idx = catch(|exn_data| unsafe {
+            __wbindgen_call(self.idx, this, args.as_ptr(), args.len(), exn_data)
+        })?;
+        Ok(JsObject::__from_idx(idx))
+    }
+}
+
+/// Invokes an intrinsic which stores an exception thrown in JS in the
+/// two-word `exn_data` out-param it's handed, turning that into an `Err`.
+fn catch<T, F>(f: F) -> Result<T, JsObject>
+    where F: FnOnce(*mut u32) -> T,
+{
+    let mut exn_data = [0; 2];
+    let ret = f(exn_data.as_mut_ptr());
+    if exn_data[0] == 1 {
+        Err(JsObject::__from_idx(exn_data[1]))
+    } else {
+        Ok(ret)
+    }
 }
 
 impl<'a> From<&'a str> for JsObject {
@@ -222,6 +456,37 @@ extern {
     fn __wbindgen_symbol_new(ptr: *const u8, len: usize) -> u32;
     fn __wbindgen_is_symbol(idx: u32) -> u32;
     fn __wbindgen_string_get(idx: u32, len: *mut usize) -> *mut u8;
+    fn __wbindgen_is_object(idx: u32) -> u32;
+    fn __wbindgen_is_function(idx: u32) -> u32;
+    fn __wbindgen_is_array(idx: u32) -> u32;
+    fn __wbindgen_typeof(idx: u32, len: *mut usize) -> *mut u8;
+    fn __wbindgen_instanceof(idx: u32, class: u32) -> u32;
+    fn __wbindgen_loose_eq(a: u32, b: u32) -> u32;
+    fn __wbindgen_strict_eq(a: u32, b: u32) -> u32;
+    fn __wbindgen_array_new() -> u32;
+    fn __wbindgen_array_push(idx: u32, value: u32);
+    fn __wbindgen_array_length(idx: u32) -> usize;
+    fn __wbindgen_array_get(idx: u32, i: usize) -> u32;
+    fn __wbindgen_cb_drop(idx: u32);
+    #[cfg(feature = "serde-serialize")]
+    fn __wbindgen_json_parse(ptr: *const u8, len: usize) -> u32;
+    #[cfg(feature = "serde-serialize")]
+    fn __wbindgen_json_serialize(idx: u32,
+                                 len: *mut usize,
+                                 exn_data: *mut u32) -> *mut u8;
+    fn __wbindgen_object_get(idx: u32, key: u32, exn_data: *mut u32) -> u32;
+    fn __wbindgen_object_set(idx: u32,
+                             key: u32,
+                             value: u32,
+                             exn_data: *mut u32) -> u32;
+    fn __wbindgen_object_has(idx: u32, key: u32, exn_data: *mut u32) -> u32;
+    fn __wbindgen_object_delete(idx: u32, key: u32, exn_data: *mut u32) -> u32;
+    fn __wbindgen_object_keys(idx: u32, exn_data: *mut u32) -> u32;
+    fn __wbindgen_call(f: u32,
+                       this: u32,
+                       args: *const u32,
+                       len: usize,
+                       exn_data: *mut u32) -> u32;
 }
 
 impl Clone for JsObject {
@@ -241,6 +506,194 @@ impl Drop for JsObject {
     }
 }
 
+/// A JS array, which can be built up and walked from Rust.
+///
+/// A `JsArray` is a `JsObject` known to be an array, and dereferences to it.
+/// Values pushed onto it are shared with JS rather than moved, so the
+/// `JsObject` handles used to build an array remain usable and are freed
+/// independently of it.
+#[derive(Clone)]
+pub struct JsArray {
+    obj: JsObject,
+}
+
+impl JsArray {
+    /// Creates a new, empty JS array.
+    pub fn new() -> JsArray {
+        unsafe {
+            JsArray { obj: JsObject::__from_idx(__wbindgen_array_new()) }
+        }
+    }
+
+    /// Interprets `obj` as an array, returning it back in `Err` if it isn't
+    /// one according to `Array.isArray`.
+    pub fn from_object(obj: JsObject) -> Result<JsArray, JsObject> {
+        if obj.is_array() {
+            Ok(JsArray { obj })
+        } else {
+            Err(obj)
+        }
+    }
+
+    /// Appends `value` to the end of this array.
+    pub fn push(&self, value: &JsObject) {
+        unsafe {
+            __wbindgen_array_push(self.obj.idx, value.idx);
+        }
+    }
+
+    /// Returns the `length` of this array.
+    pub fn len(&self) -> usize {
+        unsafe {
+            __wbindgen_array_length(self.obj.idx)
+        }
+    }
+
+    /// Tests whether this array has a `length` of zero.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the element at index `i`, or `None` if `i` is out of bounds.
+    pub fn get(&self, i: usize) -> Option<JsObject> {
+        if i >= self.len() {
+            return None
+        }
+        unsafe {
+            Some(JsObject::__from_idx(__wbindgen_array_get(self.obj.idx, i)))
+        }
+    }
+
+    /// Returns an iterator over the elements of this array.
+    ///
+    /// Each element is looked up as the iterator advances, so elements pushed
+    /// during iteration are also visited.
+    pub fn iter(&self) -> JsArrayIter {
+        JsArrayIter { array: self, next: 0 }
+    }
+}
+
+impl Default for JsArray {
+    fn default() -> JsArray {
+        JsArray::new()
+    }
+}
+
+impl Deref for JsArray {
+    type Target = JsObject;
+    fn deref(&self) -> &JsObject {
+        &self.obj
+    }
+}
+
+impl From<JsArray> for JsObject {
+    fn from(array: JsArray) -> JsObject {
+        array.obj
+    }
+}
+
+impl<'a> IntoIterator for &'a JsArray {
+    type Item = JsObject;
+    type IntoIter = JsArrayIter<'a>;
+
+    fn into_iter(self) -> JsArrayIter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the elements of a `JsArray`, created by `JsArray::iter`.
+pub struct JsArrayIter<'a> {
+    array: &'a JsArray,
+    next: usize,
+}
+
+impl<'a> Iterator for JsArrayIter<'a> {
+    type Item = JsObject;
+
+    fn next(&mut self) -> Option<JsObject> {
+        let ret = self.array.get(self.next);
+        if ret.is_some() {
+            self.next += 1;
+        }
+        ret
+    }
+}
+
+/// A value imported from JS through a `static` in an `extern "JS"` block.
+///
+/// The value is fetched from JS the first time it's accessed and then cached,
+/// after which this simply dereferences to it.
+pub struct JsStatic<T: 'static> {
+    #[doc(hidden)]
+    pub __inner: &'static LocalKey<&'static T>,
+}
+
+impl<T> Deref for JsStatic<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // The cached value is leaked rather than owned by the thread local, so
+        // the reference stays valid even after the thread that created it has
+        // exited.
+        self.__inner.with(|ptr| *ptr)
+    }
+}
+
+/// A Rust closure which can be handed to JS and called from there for as long
+/// as the `Closure` is alive.
+///
+/// `T` is the closure's trait object type, for example `FnMut(u32)`. The
+/// closure is passed to imported functions as `&Closure<FnMut(u32)>` and shows
+/// up as a plain function in JS. Once the `Closure` is dropped the JS function
+/// will throw an exception when called, unless `forget` was used to keep it
+/// alive forever.
+pub struct Closure<T: ?Sized> {
+    inner: UnsafeCell<Box<T>>,
+    js: UnsafeCell<u32>,
+}
+
+impl<T: ?Sized> Closure<T> {
+    /// Creates a new `Closure` from a boxed Rust closure, for example:
+    ///
+    /// ```ignore
+    /// let cb: Closure<FnMut(u32)> = Closure::new(Box::new(|n| { ... }));
+    /// ```
+    pub fn new(t: Box<T>) -> Closure<T> {
+        Closure {
+            inner: UnsafeCell::new(t),
+            js: UnsafeCell::new(u32::max_value()),
+        }
+    }
+
+    /// Leaks this closure, keeping the JS function valid for the rest of the
+    /// program.
+    pub fn forget(self) {
+        mem::forget(self);
+    }
+
+    #[doc(hidden)]
+    pub fn __raw(&self) -> *mut T {
+        unsafe { &mut **self.inner.get() }
+    }
+
+    /// Pointer to the index of the JS function for this closure, which the
+    /// generated JS fills in the first time the closure is passed to JS.
+    #[doc(hidden)]
+    pub fn __js_idx_ptr(&self) -> *mut u32 {
+        self.js.get()
+    }
+}
+
+impl<T: ?Sized> Drop for Closure<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let idx = *self.js.get();
+            if idx != u32::max_value() {
+                __wbindgen_cb_drop(idx);
+            }
+        }
+    }
+}
+
 /// Throws a JS exception.
 ///
 /// This function will throw a JS exception with the message provided. The
@@ -275,6 +728,15 @@ pub mod __rt {
         super::throw("null pointer passed to rust");
     }
 
+    /// Scratch space for exported functions to hand back values which don't
+    /// fit in a single wasm return value. Slot 0 is whether an `Option` was
+    /// `Some`, slot 1 whether a `Result` was `Err`, and slot 2 the index of
+    /// the error's `JsObject` in that case.
+    ///
+    /// The generated JS reads this through the
+    /// `__wbindgen_global_argument_ptr` export right after a call returns.
+    pub static mut GLOBAL_ARGUMENT: [u32; 3] = [0; 3];
+
     /// A vendored version of `RefCell` from the standard library.
     ///
     /// Now why, you may ask, would we do that? Surely `RefCell` in libstd is
@@ -131,3 +131,254 @@ fn works() {
         .test();
 }
 
+
+#[test]
+fn call() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                pub fn call0(f: &JsObject) -> JsObject {
+                    f.call0().unwrap()
+                }
+
+                pub fn call1(f: &JsObject, arg: &JsObject) -> JsObject {
+                    f.call1(arg).unwrap()
+                }
+
+                pub fn call_with(f: &JsObject, this: &JsObject) -> JsObject {
+                    let args = [JsObject::from(1), JsObject::from("a")];
+                    f.call_with(this, &args).unwrap()
+                }
+
+                pub fn call_err(f: &JsObject) -> JsObject {
+                    f.call0().unwrap_err()
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {};
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.call0(() => 3), 3);
+                assert.strictEqual(wasm.call0(function(this: any) { return this; }), undefined);
+                assert.strictEqual(wasm.call1((x: number) => x + 1, 2), 3);
+
+                const obj = { a: 10 };
+                const ret = wasm.call_with(function(this: any, x: number, y: string) {
+                    return [this.a, x, y];
+                }, obj);
+                assert.deepStrictEqual(ret, [10, 1, "a"]);
+
+                const err = new Error("oops");
+                assert.strictEqual(wasm.call_err(() => { throw err; }), err);
+                assert.ok(wasm.call_err(3) instanceof TypeError);
+            }
+        "#)
+        .test();
+}
+
+#[test]
+fn properties() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                pub fn read_config(config: &JsObject) -> JsObject {
+                    let name = config.get("name").unwrap();
+                    assert_eq!(name.as_string(), Some("foo".to_string()));
+                    assert_eq!(config.get("size").unwrap().as_f64(), Some(3.0));
+                    assert!(config.get("missing").unwrap().is_undefined());
+                    assert!(config.has("name").unwrap());
+                    assert!(!config.has("missing").unwrap());
+
+                    let keys = config.keys()
+                        .unwrap()
+                        .iter()
+                        .map(|k| k.as_string().unwrap())
+                        .collect::<Vec<_>>();
+                    assert_eq!(keys, ["name", "size"]);
+
+                    assert!(config.delete("size").unwrap());
+                    assert!(!config.has("size").unwrap());
+                    config.set("enabled", &JsObject::from(true)).unwrap();
+                    name
+                }
+
+                pub fn empty_keys(obj: &JsObject) {
+                    assert!(obj.keys().unwrap().is_empty());
+                }
+
+                pub fn throwing(obj: &JsObject) {
+                    let null = JsObject::null();
+                    assert!(null.get("x").is_err());
+                    assert!(null.set("x", &JsObject::from(1.0)).is_err());
+                    assert!(null.delete("x").is_err());
+                    assert!(null.keys().is_err());
+                    assert!(JsObject::from(1.0).has("x").is_err());
+
+                    let err = obj.get("boom").unwrap_err();
+                    assert_eq!(err.get("message").unwrap().as_string(),
+                               Some("boom".to_string()));
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {};
+
+            export function test(wasm: Exports) {
+                const config: any = { name: "foo", size: 3 };
+                assert.strictEqual(wasm.read_config(config), "foo");
+                assert.deepStrictEqual(config, { name: "foo", enabled: true });
+                wasm.empty_keys({});
+                const obj = {};
+                Object.defineProperty(obj, "boom", {
+                    get: () => { throw new Error("boom"); },
+                });
+                wasm.throwing(obj);
+            }
+        "#)
+        .test();
+}
+
+#[test]
+fn type_tests() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                pub fn kinds(
+                    obj: &JsObject,
+                    arr: &JsObject,
+                    f: &JsObject,
+                    null: &JsObject,
+                ) {
+                    assert!(obj.is_object());
+                    assert!(!obj.is_array());
+                    assert!(!obj.is_function());
+
+                    assert!(arr.is_object());
+                    assert!(arr.is_array());
+
+                    assert!(f.is_function());
+                    assert!(!f.is_object());
+
+                    assert!(!null.is_object());
+                    assert!(!JsObject::from(1).is_object());
+
+                    assert_eq!(obj.typeof_name(), "object");
+                    assert_eq!(f.typeof_name(), "function");
+                    assert_eq!(JsObject::from("a").typeof_name(), "string");
+                    assert_eq!(JsObject::undefined().typeof_name(), "undefined");
+                }
+
+                pub fn is_instance(a: &JsObject, class: &JsObject) -> bool {
+                    a.instanceof(class)
+                }
+
+                pub fn equality(a: &JsObject, b: &JsObject) {
+                    assert!(a.strict_eq(a));
+                    assert!(!a.strict_eq(b));
+
+                    let one = JsObject::from(1);
+                    assert!(one.loose_eq(&JsObject::from("1")));
+                    assert!(!one.strict_eq(&JsObject::from("1")));
+                    assert!(one.strict_eq(&JsObject::from(1)));
+                    assert!(JsObject::null().loose_eq(&JsObject::undefined()));
+                    assert!(!JsObject::null().strict_eq(&JsObject::undefined()));
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {};
+
+            class Foo {}
+
+            export function test(wasm: Exports) {
+                wasm.kinds({}, [1, 2], () => 1, null);
+
+                assert.strictEqual(wasm.is_instance(new Foo(), Foo), true);
+                assert.strictEqual(wasm.is_instance({}, Foo), false);
+                assert.strictEqual(wasm.is_instance([], Array), true);
+                assert.strictEqual(wasm.is_instance({}, 3), false);
+                assert.strictEqual(wasm.is_instance({}, {}), false);
+
+                wasm.equality({}, {});
+            }
+        "#)
+        .test();
+}
+
+#[test]
+fn arrays() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                pub fn build() -> JsObject {
+                    let array = JsArray::new();
+                    assert!(array.is_empty());
+                    let a = JsObject::from("a");
+                    array.push(&a);
+                    array.push(&a);
+                    array.push(&JsObject::from(2));
+                    assert_eq!(array.len(), 3);
+                    assert!(array.is_array());
+                    array.into()
+                }
+
+                pub fn sum(a: &JsObject) -> f64 {
+                    let array = JsArray::from_object(a.clone()).unwrap();
+                    assert!(array.get(array.len()).is_none());
+                    array.iter().map(|x| x.as_f64().unwrap()).sum()
+                }
+
+                pub fn not_array(a: JsObject) {
+                    assert!(JsArray::from_object(a).is_err());
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {};
+
+            export function test(wasm: Exports) {
+                assert.deepStrictEqual(wasm.build(), ["a", "a", 2]);
+                assert.strictEqual(wasm.sum([1, 2, 3.5]), 6.5);
+                assert.strictEqual(wasm.sum([]), 0);
+                wasm.not_array({ length: 1 });
+            }
+        "#)
+        .test();
+}
@@ -0,0 +1,90 @@
+extern crate test_support;
+
+#[test]
+fn works() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                extern "JS" {
+                    fn js_next(c: char) -> char;
+                }
+
+                pub fn upper(c: char) -> char {
+                    c.to_ascii_uppercase()
+                }
+
+                pub fn code_point(c: char) -> u32 {
+                    c as u32
+                }
+
+                pub fn crab() -> char {
+                    '🦀'
+                }
+
+                pub fn call_js() {
+                    assert_eq!(js_next('a'), 'b');
+                    assert_eq!(js_next('ß'), 'à');
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {
+                js_next(c: string): string {
+                    return String.fromCodePoint((c.codePointAt(0) as number) + 1);
+                },
+            };
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.upper("a"), "A");
+                assert.strictEqual(wasm.upper("é"), "é");
+                assert.strictEqual(wasm.code_point("🦀"), 0x1f980);
+                assert.strictEqual(wasm.crab(), "🦀");
+                assert.throws(() => wasm.upper("ab"), /expected a single character/);
+                assert.throws(() => wasm.upper(""), /expected a single character/);
+                wasm.call_js();
+            }
+        "#)
+        .test();
+}
+
+#[test]
+fn release() {
+    test_support::project()
+        .debug(false)
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                pub fn code_point(c: char) -> u32 {
+                    c as u32
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {};
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.code_point("a"), 0x61);
+                assert.strictEqual(wasm.code_point("\0"), 0);
+                assert.throws(() => wasm.code_point(""), /invalid `char` argument/);
+                assert.throws(() => (wasm as any).code_point(3), /invalid `char` argument/);
+            }
+        "#)
+        .test();
+}
@@ -228,3 +228,268 @@ fn pass_one_to_another() {
         "#)
         .test();
 }
+
+#[test]
+fn fields() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                pub struct Foo {
+                    pub a: u32,
+                    pub b: f64,
+                    pub c: bool,
+                    pub name: String,
+                    pub obj: JsObject,
+                    hidden: u32,
+                }
+
+                impl Foo {
+                    pub fn new() -> Foo {
+                        Foo {
+                            a: 1,
+                            b: 2.5,
+                            c: true,
+                            name: String::from("foo"),
+                            obj: JsObject::undefined(),
+                            hidden: 3,
+                        }
+                    }
+
+                    pub fn sum(&self) -> f64 {
+                        self.a as f64 + self.b + self.hidden as f64
+                    }
+
+                    pub fn name_len(&self) -> u32 {
+                        self.name.len() as u32
+                    }
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {};
+
+            export function test(wasm: Exports) {
+                const foo = wasm.Foo.new();
+                assert.strictEqual(foo.a, 1);
+                assert.strictEqual(foo.b, 2.5);
+                assert.strictEqual(foo.c, true);
+                assert.strictEqual(foo.name, "foo");
+                assert.strictEqual(foo.obj, undefined);
+                assert.strictEqual((foo as any).hidden, undefined);
+                assert.strictEqual(foo.sum(), 6.5);
+
+                foo.a = 10;
+                foo.b = 0.5;
+                foo.c = false;
+                foo.name = "hello";
+                const sym = Symbol('a');
+                foo.obj = sym;
+                assert.strictEqual(foo.a, 10);
+                assert.strictEqual(foo.c, false);
+                assert.strictEqual(foo.name, "hello");
+                assert.strictEqual(foo.name_len(), 5);
+                assert.strictEqual(foo.obj, sym);
+                assert.strictEqual(foo.sum(), 13.5);
+                foo.free();
+            }
+        "#)
+        .test();
+}
+
+#[test]
+fn unsupported_fields() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use std::collections::HashMap;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                pub struct Foo {
+                    pub a: u32,
+                    pub data: Vec<u8>,
+                    pub map: HashMap<String, u32>,
+                    pub maybe: Option<u32>,
+                    pub big: u64,
+                }
+
+                impl Foo {
+                    pub fn new() -> Foo {
+                        let mut map = HashMap::new();
+                        map.insert(String::from("x"), 4);
+                        Foo {
+                            a: 1,
+                            data: vec![1, 2],
+                            map,
+                            maybe: Some(3),
+                            big: 5,
+                        }
+                    }
+
+                    pub fn sum(&self) -> u32 {
+                        let data = self.data.iter().map(|&d| d as u32).sum::<u32>();
+                        self.a + data + self.map["x"] + self.maybe.unwrap() +
+                            self.big as u32
+                    }
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {};
+
+            export function test(wasm: Exports) {
+                const foo = wasm.Foo.new();
+                assert.strictEqual(foo.a, 1);
+                assert.strictEqual((foo as any).data, undefined);
+                assert.strictEqual((foo as any).map, undefined);
+                assert.strictEqual((foo as any).maybe, undefined);
+                assert.strictEqual((foo as any).big, undefined);
+                foo.a = 10;
+                assert.strictEqual(foo.sum(), 25);
+                foo.free();
+            }
+        "#)
+        .test();
+}
+
+#[test]
+fn constructors() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                pub struct Foo {
+                    contents: u32,
+                }
+
+                impl Foo {
+                    #[wasm_bindgen(constructor)]
+                    pub fn new(contents: u32) -> Foo {
+                        Foo { contents }
+                    }
+
+                    pub fn contents(&self) -> u32 {
+                        self.contents
+                    }
+
+                    pub fn double(&self) -> Foo {
+                        Foo { contents: self.contents * 2 }
+                    }
+                }
+
+                pub struct Bar {
+                    contents: u32,
+                }
+
+                impl Bar {
+                    #[wasm_bindgen(constructor)]
+                    pub fn new(contents: u32) -> Result<Bar, JsObject> {
+                        if contents == 0 {
+                            Err(JsObject::from_str("contents can't be zero"))
+                        } else {
+                            Ok(Bar { contents })
+                        }
+                    }
+
+                    pub fn contents(&self) -> u32 {
+                        self.contents
+                    }
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {};
+
+            export function test(wasm: Exports) {
+                const foo = new wasm.Foo(3);
+                assert.ok(foo instanceof wasm.Foo);
+                assert.strictEqual(foo.contents(), 3);
+
+                const foo2 = foo.double();
+                assert.ok(foo2 instanceof wasm.Foo);
+                assert.strictEqual(foo2.contents(), 6);
+                foo.free();
+                foo2.free();
+
+                const bar = new wasm.Bar(1);
+                assert.strictEqual(bar.contents(), 1);
+                bar.free();
+                assert.throws(() => new wasm.Bar(0), /contents can't be zero/);
+            }
+        "#)
+        .test();
+}
+
+#[test]
+fn consume() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                pub struct Builder {
+                    parts: Vec<String>,
+                }
+
+                impl Builder {
+                    pub fn new() -> Builder {
+                        Builder { parts: Vec::new() }
+                    }
+
+                    pub fn add(mut self, part: &str) -> Builder {
+                        self.parts.push(part.to_string());
+                        self
+                    }
+
+                    pub fn finish(self) -> String {
+                        self.parts.join(" ")
+                    }
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {};
+
+            export function test(wasm: Exports) {
+                const a = wasm.Builder.new();
+                const b = a.add("hello").add("world");
+                assert.strictEqual(a.ptr, 0);
+                assert.throws(() => a.add("foo"), /null pointer passed to rust/);
+                assert.strictEqual(b.finish(), "hello world");
+                assert.strictEqual(b.ptr, 0);
+                assert.throws(() => b.free(), /null pointer passed to rust/);
+            }
+        "#)
+        .test();
+}
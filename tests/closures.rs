@@ -0,0 +1,141 @@
+extern crate test_support;
+
+#[test]
+fn borrowed() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                extern "JS" {
+                    fn call_fn(f: &Fn(u32) -> u32, x: u32) -> u32;
+                    fn call_fn_mut(f: &mut FnMut(bool));
+                    fn call_saved() -> bool;
+                    fn call_object(f: &Fn(JsObject) -> JsObject) -> JsObject;
+                }
+
+                pub fn run() -> u32 {
+                    let y = 3;
+                    let a = call_fn(&|x| x + y, 4);
+
+                    let mut hits = 0;
+                    call_fn_mut(&mut |b| {
+                        if b {
+                            hits += 1;
+                        }
+                    });
+                    assert!(!call_saved());
+
+                    a + hits
+                }
+
+                pub fn echo() -> JsObject {
+                    call_object(&|obj| obj)
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            let SAVED: ((b: boolean) => void) | null = null;
+            const OBJ = { a: 1 };
+
+            export const imports: Imports = {
+                call_fn(f: (x: number) => number, x: number): number {
+                    return f(x) * 2;
+                },
+                call_fn_mut(f: (b: boolean) => void) {
+                    f(true);
+                    f(false);
+                    f(true);
+                    SAVED = f;
+                },
+                call_saved(): boolean {
+                    try {
+                        SAVED!(true);
+                        return true;
+                    } catch (e) {
+                        return false;
+                    }
+                },
+                call_object(f: (x: any) => any): any {
+                    return f(OBJ);
+                },
+            };
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.run(), 16);
+                assert.strictEqual(wasm.echo(), OBJ);
+            }
+        "#)
+        .test();
+}
+
+#[test]
+fn owned() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use std::cell::Cell;
+            use std::rc::Rc;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                extern "JS" {
+                    fn store(f: &Closure<FnMut(u32)>);
+                    fn call_stored(x: u32) -> bool;
+                }
+
+                pub fn run() -> u32 {
+                    let total = Rc::new(Cell::new(0));
+                    let total2 = total.clone();
+                    let cb: Closure<FnMut(u32)> = Closure::new(Box::new(move |n| {
+                        total2.set(total2.get() + n);
+                    }));
+                    store(&cb);
+                    store(&cb);
+                    assert!(call_stored(2));
+                    assert!(call_stored(5));
+                    drop(cb);
+                    assert!(!call_stored(100));
+                    total.get()
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            let STORED: ((x: number) => void) | null = null;
+
+            export const imports: Imports = {
+                store(f: (x: number) => void) {
+                    if (STORED !== null)
+                        assert.strictEqual(STORED, f);
+                    STORED = f;
+                },
+                call_stored(x: number): boolean {
+                    try {
+                        STORED!(x);
+                        return true;
+                    } catch (e) {
+                        return false;
+                    }
+                },
+            };
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.run(), 7);
+            }
+        "#)
+        .test();
+}
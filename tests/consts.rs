@@ -0,0 +1,41 @@
+extern crate test_support;
+
+#[test]
+fn simple() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                pub const MAX_SIZE: u32 = 1024;
+                pub const MIN_OFFSET: i32 = -16;
+                pub const RATIO: f64 = 0.25;
+                pub const ENABLED: bool = true;
+                pub const VERSION: &'static str = "1.2.3 \"beta\"\n";
+
+                pub fn max_size() -> u32 {
+                    MAX_SIZE
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {};
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.MAX_SIZE, 1024);
+                assert.strictEqual(wasm.max_size(), wasm.MAX_SIZE);
+                assert.strictEqual(wasm.MIN_OFFSET, -16);
+                assert.strictEqual(wasm.RATIO, 0.25);
+                assert.strictEqual(wasm.ENABLED, true);
+                assert.strictEqual(wasm.VERSION, "1.2.3 \"beta\"\n");
+            }
+        "#)
+        .test();
+}
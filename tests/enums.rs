@@ -0,0 +1,148 @@
+extern crate test_support;
+
+#[test]
+fn c_style_enum() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                pub enum Color {
+                    Green,
+                    Yellow,
+                    Red,
+                }
+
+                pub enum Status {
+                    Ok = 200,
+                    NotFound = 404,
+                    Teapot = 418,
+                    Unavailable = 503,
+                    Custom,
+                }
+
+                pub fn cycle(color: Color) -> Color {
+                    match color {
+                        Color::Green => Color::Yellow,
+                        Color::Yellow => Color::Red,
+                        Color::Red => Color::Green,
+                    }
+                }
+
+                pub fn is_error(status: Status) -> bool {
+                    match status {
+                        Status::Ok => false,
+                        _ => true,
+                    }
+                }
+
+                extern "JS" {
+                    fn js_status(code: u32) -> Status;
+                    fn js_describe(color: Color) -> u32;
+                }
+
+                pub fn status_thunk(code: u32) -> Status {
+                    js_status(code)
+                }
+
+                pub fn describe_thunk(color: Color) -> u32 {
+                    js_describe(color)
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports, Color, Status } from "./out";
+
+            export const imports: Imports = {
+                js_status(code: number): Status {
+                    return code;
+                },
+                js_describe(color: Color): number {
+                    return color;
+                },
+            };
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.Color.Green, 0);
+                assert.strictEqual(wasm.Color.Yellow, 1);
+                assert.strictEqual(wasm.Color.Red, 2);
+                assert.strictEqual(wasm.Status.Ok, 200);
+                assert.strictEqual(wasm.Status.NotFound, 404);
+                assert.strictEqual(wasm.Status.Custom, 504);
+
+                assert.strictEqual(wasm.cycle(Color.Green), Color.Yellow);
+                assert.strictEqual(wasm.cycle(wasm.Color.Red), wasm.Color.Green);
+                assert.strictEqual(wasm.is_error(Status.Ok), false);
+                assert.strictEqual(wasm.is_error(Status.Teapot), true);
+
+                assert.strictEqual(wasm.status_thunk(404), Status.NotFound);
+                assert.throws(() => wasm.status_thunk(1), /invalid enum value passed/);
+                assert.strictEqual(wasm.describe_thunk(Color.Red), 2);
+
+                assert.throws(() => wasm.cycle(7 as Color), /invalid enum value passed/);
+            }
+        "#)
+        .test();
+}
+
+#[test]
+fn negative_discriminants() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                pub enum Ordering {
+                    Less = -1,
+                    Equal,
+                    Greater,
+                }
+
+                pub fn compare(a: i32, b: i32) -> Ordering {
+                    if a < b {
+                        Ordering::Less
+                    } else if a == b {
+                        Ordering::Equal
+                    } else {
+                        Ordering::Greater
+                    }
+                }
+
+                pub fn reverse(o: Ordering) -> Ordering {
+                    match o {
+                        Ordering::Less => Ordering::Greater,
+                        Ordering::Equal => Ordering::Equal,
+                        Ordering::Greater => Ordering::Less,
+                    }
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports, Ordering } from "./out";
+
+            export const imports: Imports = {};
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.Ordering.Less, -1);
+                assert.strictEqual(wasm.Ordering.Equal, 0);
+                assert.strictEqual(wasm.Ordering.Greater, 1);
+
+                assert.strictEqual(wasm.compare(1, 2), Ordering.Less);
+                assert.strictEqual(wasm.compare(2, 2), Ordering.Equal);
+                assert.strictEqual(wasm.reverse(Ordering.Less), Ordering.Greater);
+                assert.strictEqual(wasm.reverse(Ordering.Greater), Ordering.Less);
+                assert.throws(() => wasm.reverse(-2 as Ordering), /invalid enum value passed/);
+            }
+        "#)
+        .test();
+}
@@ -0,0 +1,92 @@
+extern crate test_support;
+
+#[test]
+fn simple() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                extern "JS" {
+                    type Counter;
+
+                    #[wasm_bindgen(constructor)]
+                    fn new(start: u32) -> Counter;
+                    #[wasm_bindgen(method)]
+                    fn increment(this: &Counter, by: u32) -> u32;
+                    #[wasm_bindgen(method)]
+                    fn same(this: &Counter, other: &Counter) -> bool;
+                    #[wasm_bindgen(static_method_of = "Counter")]
+                    fn instances() -> u32;
+                }
+
+                pub fn run() -> u32 {
+                    let a = Counter::new(3);
+                    let b = Counter::new(10);
+                    assert!(a.same(&a));
+                    assert!(!a.same(&b));
+                    a.increment(1) + b.increment(2)
+                }
+
+                pub fn make_counter(start: u32) -> Counter {
+                    Counter::new(start)
+                }
+
+                pub fn bump(c: &Counter) -> u32 {
+                    c.increment(5)
+                }
+
+                pub fn count() -> u32 {
+                    Counter::instances()
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            class Counter {
+                static count = 0;
+                value: number;
+
+                constructor(start: number) {
+                    Counter.count += 1;
+                    this.value = start;
+                }
+
+                increment(by: number): number {
+                    this.value += by;
+                    return this.value;
+                }
+
+                same(other: Counter): boolean {
+                    return this === other;
+                }
+
+                static instances(): number {
+                    return Counter.count;
+                }
+            }
+
+            export const imports: Imports = {
+                Counter,
+            };
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.run(), 16);
+                assert.strictEqual(wasm.count(), 2);
+
+                const c = wasm.make_counter(1);
+                assert.ok(c instanceof Counter);
+                assert.strictEqual(c.value, 1);
+                assert.strictEqual(wasm.bump(c), 6);
+                assert.strictEqual(c.value, 6);
+                assert.strictEqual(wasm.count(), 3);
+            }
+        "#)
+        .test();
+}
@@ -107,3 +107,378 @@ fn unused() {
         "#)
         .test();
 }
+
+#[test]
+fn strings() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                extern "JS" {
+                    fn foo(a: String) -> String;
+                    fn bar(a: &str) -> String;
+                }
+
+                pub fn test_foo() {
+                    assert_eq!(foo("a".to_string()), "ab");
+                }
+
+                pub fn test_bar() -> String {
+                    bar("x")
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import { Exports, Imports } from "./out";
+            import * as assert from "assert";
+
+            export const imports: Imports = {
+                foo(a: string): string {
+                    return a + "b";
+                },
+                bar(a: string): string {
+                    return a + "y";
+                },
+            };
+
+            export function test(wasm: Exports) {
+                wasm.test_foo();
+                assert.strictEqual(wasm.test_bar(), "xy");
+            }
+        "#)
+        .test();
+}
+
+#[test]
+fn structs() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                pub struct Scene {
+                    contents: u32,
+                }
+
+                impl Scene {
+                    pub fn contents(&self) -> u32 {
+                        self.contents
+                    }
+                }
+
+                extern "JS" {
+                    fn render(s: Scene) -> Scene;
+                }
+
+                pub fn run() -> u32 {
+                    let scene = render(Scene { contents: 3 });
+                    scene.contents
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import { Exports, Imports, Scene } from "./out";
+            import * as assert from "assert";
+
+            export const imports: Imports = {
+                render(s: Scene): Scene {
+                    assert.strictEqual(s.contents(), 3);
+                    return s;
+                },
+            };
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.run(), 3);
+            }
+        "#)
+        .test();
+}
+
+#[test]
+fn catch() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                extern "JS" {
+                    #[wasm_bindgen(catch)]
+                    fn maybe_throw(a: u32) -> Result<u32, JsObject>;
+                    #[wasm_bindgen(catch)]
+                    fn maybe_throw_string(s: &str) -> Result<String, JsObject>;
+                    #[wasm_bindgen(catch)]
+                    fn always_throw() -> Result<(), JsObject>;
+                }
+
+                pub fn call(a: u32) -> u32 {
+                    match maybe_throw(a) {
+                        Ok(n) => n,
+                        Err(e) => e.as_f64().unwrap() as u32 + 100,
+                    }
+                }
+
+                pub fn call_string(s: &str) -> String {
+                    match maybe_throw_string(s) {
+                        Ok(s) => s,
+                        Err(e) => format!("caught {}", e.as_string().unwrap()),
+                    }
+                }
+
+                pub fn call_always() -> JsObject {
+                    always_throw().unwrap_err()
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            const ERR = Symbol('error');
+
+            export const imports: Imports = {
+                maybe_throw(a: number): number {
+                    if (a === 0)
+                        throw 1;
+                    return a * 2;
+                },
+                maybe_throw_string(s: string): string {
+                    if (s === "")
+                        throw "empty";
+                    return s + "!";
+                },
+                always_throw(): void {
+                    throw ERR;
+                },
+            };
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.call(3), 6);
+                assert.strictEqual(wasm.call(0), 101);
+
+                assert.strictEqual(wasm.call_string("foo"), "foo!");
+                assert.strictEqual(wasm.call_string(""), "caught empty");
+
+                assert.strictEqual(wasm.call_always(), ERR);
+            }
+        "#)
+        .test();
+}
+
+#[test]
+fn statics() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                extern "JS" {
+                    type Config;
+
+                    #[wasm_bindgen(method)]
+                    fn name(this: &Config) -> String;
+
+                    static CONFIG: Config;
+                    static LIMIT: u32;
+                    static VERBOSE: bool;
+                    static OBJECT: JsObject;
+
+                    fn fetched() -> u32;
+                }
+
+                pub fn config_name() -> String {
+                    CONFIG.name()
+                }
+
+                pub fn limit() -> u32 {
+                    *LIMIT + *LIMIT
+                }
+
+                pub fn verbose() -> bool {
+                    *VERBOSE
+                }
+
+                pub fn object() -> JsObject {
+                    OBJECT.clone()
+                }
+
+                pub fn fetched_thunk() -> u32 {
+                    fetched()
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            let FETCHED = 0;
+            const OBJECT = { a: 1 };
+
+            export const imports: Imports = {
+                get CONFIG() {
+                    FETCHED += 1;
+                    return { name: () => "config" };
+                },
+                LIMIT: 21,
+                VERBOSE: true,
+                OBJECT,
+                fetched(): number {
+                    return FETCHED;
+                },
+            };
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.config_name(), "config");
+                assert.strictEqual(wasm.config_name(), "config");
+                assert.strictEqual(wasm.fetched_thunk(), 1);
+                assert.strictEqual(wasm.limit(), 42);
+                assert.strictEqual(wasm.verbose(), true);
+                assert.strictEqual(wasm.object(), OBJECT);
+            }
+        "#)
+        .test();
+}
+
+#[test]
+fn from_module() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                #[wasm_bindgen(module = "./foo")]
+                extern "JS" {
+                    fn add(a: u32, b: u32) -> u32;
+                    fn greet(name: &str) -> String;
+
+                    type Point;
+
+                    #[wasm_bindgen(constructor)]
+                    fn new(x: u32, y: u32) -> Point;
+                    #[wasm_bindgen(method)]
+                    fn sum(this: &Point) -> u32;
+
+                    static ORIGIN: Point;
+                }
+
+                extern "JS" {
+                    fn local(a: u32) -> u32;
+                }
+
+                pub fn run() -> u32 {
+                    let p = Point::new(1, 2);
+                    add(p.sum(), ORIGIN.sum()) + local(10)
+                }
+
+                pub fn greet_thunk(name: &str) -> String {
+                    greet(name)
+                }
+            }
+        "#)
+        .file("foo.ts", r#"
+            export function add(a: number, b: number): number {
+                return a + b;
+            }
+
+            export function greet(name: string): string {
+                return `Hello, ${name}!`;
+            }
+
+            export class Point {
+                x: number;
+                y: number;
+
+                constructor(x: number, y: number) {
+                    this.x = x;
+                    this.y = y;
+                }
+
+                sum(): number {
+                    return this.x + this.y;
+                }
+            }
+
+            export const ORIGIN = new Point(100, 200);
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {
+                local(a: number): number {
+                    return a * 2;
+                },
+            };
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.run(), 323);
+                assert.strictEqual(wasm.greet_thunk("wasm"), "Hello, wasm!");
+            }
+        "#)
+        .test();
+}
+
+#[test]
+fn module_names_clash_with_glue() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+            #![allow(non_snake_case)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                #[wasm_bindgen(module = "./helpers")]
+                extern "JS" {
+                    fn getStringFromWasm(s: &str) -> String;
+                    fn passStringToWasm(n: u32) -> u32;
+                }
+
+                pub fn run(s: &str) -> String {
+                    format!("{} {}", getStringFromWasm(s), passStringToWasm(1))
+                }
+            }
+        "#)
+        .file("helpers.ts", r#"
+            export function getStringFromWasm(s: string): string {
+                return s.toUpperCase();
+            }
+
+            export function passStringToWasm(n: number): number {
+                return n + 1;
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {};
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.run("foo"), "FOO 2");
+            }
+        "#)
+        .test();
+}
@@ -0,0 +1,129 @@
+extern crate test_support;
+
+#[test]
+fn works() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                extern "JS" {
+                    fn js_add(a: i64, b: i64) -> i64;
+                    fn js_identity(a: u64) -> u64;
+                }
+
+                pub fn add(a: i64, b: i64) -> i64 {
+                    a + b
+                }
+
+                pub fn max_safe() -> u64 {
+                    (1 << 53) - 1
+                }
+
+                pub fn high_bits(a: u64) -> u32 {
+                    (a >> 32) as u32
+                }
+
+                pub fn call_js() {
+                    assert_eq!(js_add(-(1 << 40), 3), -(1 << 40) + 3);
+                    assert_eq!(js_add(-1, 0), -1);
+                    assert_eq!(js_identity(1 << 52), 1 << 52);
+                    assert_eq!(js_identity(0xffff_ffff), 0xffff_ffff);
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {
+                js_add(a: number, b: number): number {
+                    return a + b;
+                },
+                js_identity(a: number): number {
+                    return a;
+                },
+            };
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.add(1, 2), 3);
+                assert.strictEqual(wasm.add(-5, 2), -3);
+                assert.strictEqual(wasm.add(Math.pow(2, 40), Math.pow(2, 40)), Math.pow(2, 41));
+                assert.strictEqual(wasm.add(-Math.pow(2, 40), 0), -Math.pow(2, 40));
+                assert.strictEqual(wasm.max_safe(), Number.MAX_SAFE_INTEGER);
+                assert.strictEqual(wasm.high_bits(Math.pow(2, 35)), 8);
+                assert.throws(() => wasm.add(1.5, 0));
+                wasm.call_js();
+            }
+        "#)
+        .test();
+}
+
+#[test]
+fn bigint() {
+    test_support::project()
+        .bigint(true)
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                extern "JS" {
+                    fn js_add(a: i64, b: i64) -> i64;
+                    fn js_identity(a: u64) -> u64;
+                }
+
+                pub fn add(a: i64, b: i64) -> i64 {
+                    a.wrapping_add(b)
+                }
+
+                pub fn identity(a: u64) -> u64 {
+                    a
+                }
+
+                pub fn min() -> i64 {
+                    i64::min_value()
+                }
+
+                pub fn call_js() {
+                    assert_eq!(js_add(-(1 << 60), -1), -(1 << 60) - 1);
+                    assert_eq!(js_add(i64::max_value(), 0), i64::max_value());
+                    assert_eq!(js_identity(u64::max_value()), u64::max_value());
+                    assert_eq!(js_identity((1 << 53) + 1), (1 << 53) + 1);
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {
+                js_add(a: bigint, b: bigint): bigint {
+                    return a + b;
+                },
+                js_identity(a: bigint): bigint {
+                    return a;
+                },
+            };
+
+            export function test(wasm: Exports) {
+                const big = BigInt("9007199254740993"); // 2^53 + 1
+                assert.strictEqual(wasm.add(big, BigInt(1)), BigInt("9007199254740994"));
+                assert.strictEqual(wasm.add(-big, BigInt(-1)), BigInt("-9007199254740994"));
+                assert.strictEqual(wasm.add(BigInt(-5), BigInt(2)), BigInt(-3));
+                assert.strictEqual(wasm.identity(big), big);
+                assert.strictEqual(wasm.identity(BigInt("18446744073709551615")),
+                                   BigInt("18446744073709551615"));
+                assert.strictEqual(wasm.min(), BigInt("-9223372036854775808"));
+                wasm.call_js();
+            }
+        "#)
+        .test();
+}
@@ -0,0 +1,134 @@
+extern crate test_support;
+
+#[test]
+fn works() {
+    test_support::project()
+        .js(true)
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                extern "JS" {
+                    fn foo(s: &str) -> u32;
+                }
+
+                pub struct A {
+                    contents: u32,
+                }
+
+                impl A {
+                    pub fn new(s: &str) -> A {
+                        A { contents: foo(s) }
+                    }
+
+                    pub fn contents(&self) -> u32 {
+                        self.contents
+                    }
+                }
+
+                pub fn clone(a: &JsObject) -> JsObject {
+                    a.clone()
+                }
+
+                pub fn concat(a: &str, b: &str) -> String {
+                    format!("{}{}", a, b)
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {
+                foo(s: string): number {
+                    return s.length;
+                },
+            };
+
+            export function test(wasm: Exports) {
+                let sym = Symbol('a');
+                assert.strictEqual(wasm.clone(sym), sym);
+                assert.strictEqual(wasm.concat("a", "b"), "ab");
+                let a = wasm.A.new("foo");
+                assert.strictEqual(a.contents(), 3);
+                a.free();
+            }
+        "#)
+        .test();
+}
+
+const MODULE_SRC: &str = r#"
+    #![feature(proc_macro)]
+
+    extern crate wasm_bindgen;
+
+    use wasm_bindgen::prelude::*;
+
+    wasm_bindgen! {
+        extern "JS" {
+            fn greeting() -> String;
+        }
+
+        pub struct Counter {
+            count: u32,
+        }
+
+        impl Counter {
+            pub fn new() -> Counter {
+                Counter { count: 0 }
+            }
+
+            pub fn bump(&mut self) -> u32 {
+                self.count += 1;
+                self.count
+            }
+        }
+
+        pub fn greet(name: &str) -> String {
+            format!("{}, {}!", greeting(), name)
+        }
+    }
+"#;
+
+const MODULE_TEST: &str = r#"
+    import * as assert from "assert";
+    import { Exports, Imports } from "./out";
+
+    export const imports: Imports = {
+        greeting(): string {
+            return "Hello";
+        },
+    };
+
+    export function test(wasm: Exports) {
+        assert.strictEqual(wasm.greet("wasm"), "Hello, wasm!");
+        const c = wasm.Counter.new();
+        assert.strictEqual(c.bump(), 1);
+        assert.strictEqual(c.bump(), 2);
+        c.free();
+    }
+"#;
+
+#[test]
+fn nodejs_module() {
+    test_support::project()
+        .js(true)
+        .nodejs(true)
+        .file("src/lib.rs", MODULE_SRC)
+        .file("test.ts", MODULE_TEST)
+        .test();
+}
+
+#[test]
+fn es_module() {
+    test_support::project()
+        .js(true)
+        .nodejs(false)
+        .file("src/lib.rs", MODULE_SRC)
+        .file("test.ts", MODULE_TEST)
+        .test();
+}
@@ -0,0 +1,158 @@
+extern crate test_support;
+
+#[test]
+fn export() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                pub fn double(a: Option<u32>) -> Option<u32> {
+                    a.map(|a| a * 2)
+                }
+
+                pub fn sum(a: Option<u32>, b: Option<u32>) -> u32 {
+                    a.unwrap_or(100) + b.unwrap_or(1000)
+                }
+
+                pub fn not(a: Option<bool>) -> Option<bool> {
+                    a.map(|a| !a)
+                }
+
+                pub fn len(a: Option<&str>) -> Option<u32> {
+                    a.map(|a| a.len() as u32)
+                }
+
+                pub fn shout(a: Option<String>) -> Option<String> {
+                    a.map(|a| a.to_uppercase())
+                }
+
+                pub fn echo(a: Option<JsObject>) -> Option<JsObject> {
+                    a
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {};
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.double(3), 6);
+                assert.strictEqual(wasm.double(0), 0);
+                assert.strictEqual(wasm.double(undefined), undefined);
+
+                assert.strictEqual(wasm.sum(1, 2), 3);
+                assert.strictEqual(wasm.sum(undefined, 2), 102);
+                assert.strictEqual(wasm.sum(1, undefined), 1001);
+                assert.strictEqual(wasm.sum(undefined, undefined), 1100);
+
+                assert.strictEqual(wasm.not(true), false);
+                assert.strictEqual(wasm.not(false), true);
+                assert.strictEqual(wasm.not(undefined), undefined);
+
+                assert.strictEqual(wasm.len("foo"), 3);
+                assert.strictEqual(wasm.len(""), 0);
+                assert.strictEqual(wasm.len(undefined), undefined);
+
+                assert.strictEqual(wasm.shout("foo"), "FOO");
+                assert.strictEqual(wasm.shout(undefined), undefined);
+
+                const sym = Symbol('a');
+                assert.strictEqual(wasm.echo(sym), sym);
+                assert.strictEqual(wasm.echo(undefined), undefined);
+            }
+        "#)
+        .test();
+}
+
+#[test]
+fn import() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                pub struct Foo {
+                    contents: u32,
+                }
+
+                impl Foo {
+                    pub fn new(contents: u32) -> Foo {
+                        Foo { contents }
+                    }
+
+                    pub fn contents(&self) -> u32 {
+                        self.contents
+                    }
+                }
+
+                extern "JS" {
+                    fn js_number(a: Option<f64>) -> Option<f64>;
+                    fn js_bool(a: Option<bool>) -> Option<bool>;
+                    fn js_string(a: Option<&str>) -> Option<String>;
+                    fn js_foo(a: Option<Foo>) -> Option<Foo>;
+                }
+
+                pub fn number_thunk(a: Option<f64>) -> Option<f64> {
+                    js_number(a)
+                }
+
+                pub fn bool_thunk(a: Option<bool>) -> Option<bool> {
+                    js_bool(a)
+                }
+
+                pub fn string_thunk(a: Option<String>) -> Option<String> {
+                    js_string(a.as_ref().map(|s| &s[..]))
+                }
+
+                pub fn foo_thunk(a: Option<u32>) -> Option<u32> {
+                    js_foo(a.map(Foo::new)).map(|f| f.contents)
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports, Foo } from "./out";
+
+            export const imports: Imports = {
+                js_number(a: number | undefined): number | undefined {
+                    return a === undefined ? undefined : a + 1;
+                },
+                js_bool(a: boolean | undefined): boolean | undefined {
+                    return a;
+                },
+                js_string(a: string | undefined): string | undefined {
+                    return a === undefined ? undefined : a + "!";
+                },
+                js_foo(a: Foo | undefined): Foo | undefined {
+                    return a;
+                },
+            };
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.number_thunk(1.5), 2.5);
+                assert.strictEqual(wasm.number_thunk(undefined), undefined);
+
+                assert.strictEqual(wasm.bool_thunk(true), true);
+                assert.strictEqual(wasm.bool_thunk(false), false);
+                assert.strictEqual(wasm.bool_thunk(undefined), undefined);
+
+                assert.strictEqual(wasm.string_thunk("foo"), "foo!");
+                assert.strictEqual(wasm.string_thunk(undefined), undefined);
+
+                assert.strictEqual(wasm.foo_thunk(3), 3);
+                assert.strictEqual(wasm.foo_thunk(undefined), undefined);
+            }
+        "#)
+        .test();
+}
@@ -0,0 +1,81 @@
+extern crate test_support;
+
+#[test]
+fn export() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                pub struct Foo {
+                    contents: u32,
+                }
+
+                impl Foo {
+                    pub fn contents(&self) -> u32 {
+                        self.contents
+                    }
+                }
+
+                pub fn checked_div(a: u32, b: u32) -> Result<u32, JsObject> {
+                    if b == 0 {
+                        Err(JsObject::from_str("division by zero"))
+                    } else {
+                        Ok(a / b)
+                    }
+                }
+
+                pub fn parse(s: &str) -> Result<String, &'static str> {
+                    if s.is_empty() {
+                        Err("empty string")
+                    } else {
+                        Ok(s.to_uppercase())
+                    }
+                }
+
+                pub fn check(ok: bool) -> Result<(), u32> {
+                    if ok { Ok(()) } else { Err(7) }
+                }
+
+                pub fn make_foo(contents: u32) -> Result<Foo, JsObject> {
+                    if contents == 0 {
+                        Err(JsObject::from_str("no contents"))
+                    } else {
+                        Ok(Foo { contents })
+                    }
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {};
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.checked_div(6, 3), 2);
+                assert.throws(() => wasm.checked_div(1, 0), /division by zero/);
+
+                assert.strictEqual(wasm.parse("foo"), "FOO");
+                assert.throws(() => wasm.parse(""), /empty string/);
+
+                wasm.check(true);
+                try {
+                    wasm.check(false);
+                    throw new Error("should have thrown");
+                } catch (e) {
+                    assert.strictEqual(e, 7);
+                }
+
+                const foo = wasm.make_foo(3);
+                assert.strictEqual(foo.contents(), 3);
+                foo.free();
+                assert.throws(() => wasm.make_foo(0), /no contents/);
+            }
+        "#)
+        .test();
+}
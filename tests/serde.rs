@@ -0,0 +1,85 @@
+extern crate test_support;
+
+#[test]
+fn serde() {
+    test_support::project()
+        .serde(true)
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+            #[macro_use]
+            extern crate serde_derive;
+
+            use std::collections::BTreeMap;
+
+            use wasm_bindgen::prelude::*;
+
+            #[derive(Serialize, Deserialize, Debug, PartialEq)]
+            pub struct Config {
+                name: String,
+                size: u32,
+                tags: Vec<String>,
+                extra: Option<BTreeMap<String, f64>>,
+            }
+
+            wasm_bindgen! {
+                pub fn make() -> JsObject {
+                    let mut extra = BTreeMap::new();
+                    extra.insert("a".to_string(), 1.5);
+                    JsObject::from_serde(&Config {
+                        name: "foo".to_string(),
+                        size: 3,
+                        tags: vec!["x".to_string(), "y".to_string()],
+                        extra: Some(extra),
+                    }).unwrap()
+                }
+
+                pub fn read(a: &JsObject) -> u32 {
+                    let config: Config = a.into_serde().unwrap();
+                    assert_eq!(config.name, "bar");
+                    assert_eq!(config.tags, ["z"]);
+                    assert_eq!(config.extra, None);
+                    config.size
+                }
+
+                pub fn read_invalid(a: &JsObject) {
+                    assert!(a.into_serde::<Config>().is_err());
+                }
+
+                pub fn read_undefined() {
+                    let none: Option<u32> = JsObject::undefined().into_serde().unwrap();
+                    assert_eq!(none, None);
+                }
+
+                pub fn read_unserializable(a: &JsObject) {
+                    assert!(a.into_serde::<Option<u32>>().is_err());
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {};
+
+            export function test(wasm: Exports) {
+                assert.deepStrictEqual(wasm.make(), {
+                    name: "foo",
+                    size: 3,
+                    tags: ["x", "y"],
+                    extra: { a: 1.5 },
+                });
+                assert.strictEqual(wasm.read({ name: "bar", size: 4, tags: ["z"], extra: null }), 4);
+                wasm.read_invalid({ name: 3 });
+                wasm.read_undefined();
+
+                const cyclic: any = {};
+                cyclic.self = cyclic;
+                wasm.read_unserializable(cyclic);
+                wasm.read_unserializable(() => 1);
+                wasm.read_unserializable(Symbol("a"));
+            }
+        "#)
+        .test();
+}
@@ -0,0 +1,249 @@
+extern crate test_support;
+
+#[test]
+fn export() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                pub fn sum(a: &[u8]) -> u32 {
+                    a.iter().map(|x| *x as u32).sum()
+                }
+
+                pub fn double(a: &mut [u8]) {
+                    for x in a.iter_mut() {
+                        *x *= 2;
+                    }
+                }
+
+                pub fn reverse(mut a: Vec<u8>) -> Vec<u8> {
+                    a.reverse();
+                    a
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {};
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.sum(new Uint8Array([1, 2, 3])), 6);
+                assert.strictEqual(wasm.sum(new Uint8Array([])), 0);
+
+                const a = new Uint8Array([1, 2, 3]);
+                wasm.double(a);
+                assert.deepStrictEqual(a, new Uint8Array([2, 4, 6]));
+
+                const b = wasm.reverse(new Uint8Array([1, 2, 3]));
+                assert.deepStrictEqual(b, new Uint8Array([3, 2, 1]));
+            }
+        "#)
+        .test();
+}
+
+#[test]
+fn import() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                extern "JS" {
+                    fn js_sum(a: &[u8]) -> u32;
+                    fn js_fill(a: &mut [u8], b: u8);
+                    fn js_reverse(a: Vec<u8>) -> Vec<u8>;
+                }
+
+                pub fn sum() -> u32 {
+                    js_sum(&[1, 2, 3])
+                }
+
+                pub fn fill() {
+                    let mut a = [0; 3];
+                    js_fill(&mut a, 4);
+                    assert_eq!(a, [4, 4, 4]);
+                }
+
+                pub fn reverse() {
+                    assert_eq!(js_reverse(vec![1, 2, 3]), [3, 2, 1]);
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {
+                js_sum(a: Uint8Array): number {
+                    return a.reduce((a, b) => a + b, 0);
+                },
+                js_fill(a: Uint8Array, b: number) {
+                    a.fill(b);
+                },
+                js_reverse(a: Uint8Array): Uint8Array {
+                    return a.reverse();
+                },
+            };
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.sum(), 6);
+                wasm.fill();
+                wasm.reverse();
+            }
+        "#)
+        .test();
+}
+
+#[test]
+fn typed() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            wasm_bindgen! {
+                extern "JS" {
+                    fn js_scale(a: &mut [f32], b: f32);
+                    fn js_halve(a: &[f64]) -> Vec<f64>;
+                }
+
+                pub fn sum_i32(a: &[i32]) -> i32 {
+                    a.iter().sum()
+                }
+
+                pub fn negate_i16(a: &mut [i16]) {
+                    for x in a.iter_mut() {
+                        *x = -*x;
+                    }
+                }
+
+                pub fn double_f64(a: Vec<f64>) -> Vec<f64> {
+                    a.into_iter().map(|x| x * 2.0).collect()
+                }
+
+                pub fn range_u32(n: u32) -> Vec<u32> {
+                    (0..n).collect()
+                }
+
+                pub fn scale() {
+                    let mut a = [1.0, 2.0];
+                    js_scale(&mut a, 1.5);
+                    assert_eq!(a, [1.5, 3.0]);
+                }
+
+                pub fn halve() {
+                    assert_eq!(js_halve(&[2.0, 5.0]), [1.0, 2.5]);
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {
+                js_scale(a: Float32Array, b: number) {
+                    for (let i = 0; i < a.length; i++)
+                        a[i] *= b;
+                },
+                js_halve(a: Float64Array): Float64Array {
+                    return a.map(x => x / 2);
+                },
+            };
+
+            export function test(wasm: Exports) {
+                assert.strictEqual(wasm.sum_i32(new Int32Array([1, -2, 3])), 2);
+
+                const a = new Int16Array([1, -2, 3]);
+                wasm.negate_i16(a);
+                assert.deepStrictEqual(a, new Int16Array([-1, 2, -3]));
+
+                const b = wasm.double_f64(new Float64Array([0.5, 1.5]));
+                assert.deepStrictEqual(b, new Float64Array([1, 3]));
+
+                assert.deepStrictEqual(wasm.range_u32(3), new Uint32Array([0, 1, 2]));
+
+                assert.throws(() => wasm.sum_i32(new Uint8Array([1]) as any),
+                              /expected a Int32Array argument/);
+
+                wasm.scale();
+                wasm.halve();
+            }
+        "#)
+        .test();
+}
+
+#[test]
+fn aligned() {
+    test_support::project()
+        .file("src/lib.rs", r#"
+            #![feature(proc_macro)]
+
+            extern crate wasm_bindgen;
+
+            use wasm_bindgen::prelude::*;
+
+            fn aligned<T>(p: *const T) -> bool {
+                p as usize % std::mem::align_of::<T>() == 0
+            }
+
+            wasm_bindgen! {
+                extern "JS" {
+                    fn halves(s: &str) -> Vec<f64>;
+                }
+
+                pub fn sum(s: &str, a: &[f64], b: Vec<f64>, c: &mut [u32]) -> f64 {
+                    assert!(aligned(a.as_ptr()));
+                    assert!(aligned(b.as_ptr()));
+                    assert!(aligned(c.as_ptr()));
+                    for x in c.iter_mut() {
+                        *x += 1;
+                    }
+                    s.len() as f64 + a.iter().sum::<f64>() + b.iter().sum::<f64>()
+                }
+
+                pub fn from_js() -> f64 {
+                    let v = halves("abc");
+                    assert!(aligned(v.as_ptr()));
+                    assert!(halves("").is_empty());
+                    v.iter().sum()
+                }
+            }
+        "#)
+        .file("test.ts", r#"
+            import * as assert from "assert";
+            import { Exports, Imports } from "./out";
+
+            export const imports: Imports = {
+                halves(s: string): Float64Array {
+                    return new Float64Array(Array.from(s).map(() => 0.5));
+                },
+            };
+
+            export function test(wasm: Exports) {
+                const c = new Uint32Array([1, 2]);
+                const a = new Float64Array([1.5, 2]);
+                const b = new Float64Array([0.25]);
+                assert.strictEqual(wasm.sum("abc", a, b, c), 6.75);
+                assert.deepStrictEqual(Array.from(c), [2, 3]);
+
+                const empty = new Float64Array([]);
+                assert.strictEqual(wasm.sum("a", empty, empty, new Uint32Array([])), 1);
+                assert.strictEqual(wasm.from_js(), 1.5);
+            }
+        "#)
+        .test();
+}